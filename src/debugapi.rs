@@ -0,0 +1,301 @@
+use crate::handleapi::Handle;
+use std::mem::MaybeUninit;
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::winerror::ERROR_SEM_TIMEOUT;
+use winapi::um::debugapi::ContinueDebugEvent;
+use winapi::um::debugapi::WaitForDebugEvent;
+use winapi::um::minwinbase::CREATE_PROCESS_DEBUG_EVENT;
+use winapi::um::minwinbase::CREATE_THREAD_DEBUG_EVENT;
+use winapi::um::minwinbase::DEBUG_EVENT;
+use winapi::um::minwinbase::EXCEPTION_DEBUG_EVENT;
+use winapi::um::minwinbase::EXIT_PROCESS_DEBUG_EVENT;
+use winapi::um::minwinbase::EXIT_THREAD_DEBUG_EVENT;
+use winapi::um::minwinbase::LOAD_DLL_DEBUG_EVENT;
+use winapi::um::minwinbase::OUTPUT_DEBUG_STRING_EVENT;
+use winapi::um::minwinbase::RIP_EVENT;
+use winapi::um::minwinbase::UNLOAD_DLL_DEBUG_EVENT;
+use winapi::um::winnt::DBG_CONTINUE;
+use winapi::um::winnt::DBG_EXCEPTION_NOT_HANDLED;
+use winapi::um::winnt::HANDLE;
+
+/// A debug event reported by a debuggee.
+#[derive(Debug)]
+pub struct DebugEvent {
+    /// The PID of the process the event occured in
+    pub pid: u32,
+
+    /// The TID of the thread the event occured in
+    pub tid: u32,
+
+    /// The type of event, along with its data
+    pub kind: DebugEventKind,
+}
+
+/// The type of a [`DebugEvent`].
+///
+/// Addresses are in the address space of the debuggee.
+#[derive(Debug)]
+pub enum DebugEventKind {
+    /// An exception occured in the debuggee
+    Exception {
+        /// The exception code
+        code: u32,
+
+        /// The address where the exception occured
+        address: usize,
+
+        /// Whether the debugger is seeing this exception for the first time
+        first_chance: bool,
+    },
+
+    /// A new thread was created in the debuggee
+    CreateThread {
+        /// The starting address of the thread
+        start_address: usize,
+    },
+
+    /// A new process was created, or the debugger was attached to an existing process
+    CreateProcess {
+        /// A handle to the image file of the process, if it is available
+        file: Option<Handle>,
+
+        /// The base address of the image
+        base_of_image: usize,
+    },
+
+    /// A thread exited
+    ExitThread {
+        /// The exit code of the thread
+        exit_code: u32,
+    },
+
+    /// The debuggee exited
+    ExitProcess {
+        /// The exit code of the process
+        exit_code: u32,
+    },
+
+    /// A dll was loaded
+    LoadDll {
+        /// A handle to the dll file, if it is available
+        file: Option<Handle>,
+
+        /// The base address of the dll
+        base_of_dll: usize,
+    },
+
+    /// A dll was unloaded
+    UnloadDll {
+        /// The base address of the dll
+        base_of_dll: usize,
+    },
+
+    /// The debuggee called `OutputDebugString`
+    OutputDebugString {
+        /// The address of the string
+        address: usize,
+
+        /// The length of the string in characters, including the NUL terminator
+        len: u16,
+
+        /// Whether the string is a wide string
+        unicode: bool,
+    },
+
+    /// The debuggee died outside of the control of the debugger
+    Rip {
+        /// The error that caused the RIP event
+        error: u32,
+
+        /// The type of the error
+        kind: u32,
+    },
+
+    /// An event this library does not know about
+    Unknown(u32),
+}
+
+impl DebugEvent {
+    /// Make a [`DebugEvent`] from a `DEBUG_EVENT`.
+    ///
+    /// # Safety
+    /// `event` must be a valid `DEBUG_EVENT` returned from `WaitForDebugEvent`.
+    /// Any file handles in `event` will be owned by the returned [`DebugEvent`].
+    pub unsafe fn from_raw(event: DEBUG_EVENT) -> Self {
+        let kind = match event.dwDebugEventCode {
+            EXCEPTION_DEBUG_EVENT => {
+                let info = event.u.Exception();
+                DebugEventKind::Exception {
+                    code: info.ExceptionRecord.ExceptionCode,
+                    address: info.ExceptionRecord.ExceptionAddress as usize,
+                    first_chance: info.dwFirstChance != 0,
+                }
+            }
+            CREATE_THREAD_DEBUG_EVENT => {
+                let info = event.u.CreateThread();
+                DebugEventKind::CreateThread {
+                    start_address: info.lpStartAddress.map_or(0, |f| f as usize),
+                }
+            }
+            CREATE_PROCESS_DEBUG_EVENT => {
+                let info = event.u.CreateProcessInfo();
+                DebugEventKind::CreateProcess {
+                    file: file_handle(info.hFile),
+                    base_of_image: info.lpBaseOfImage as usize,
+                }
+            }
+            EXIT_THREAD_DEBUG_EVENT => DebugEventKind::ExitThread {
+                exit_code: event.u.ExitThread().dwExitCode,
+            },
+            EXIT_PROCESS_DEBUG_EVENT => DebugEventKind::ExitProcess {
+                exit_code: event.u.ExitProcess().dwExitCode,
+            },
+            LOAD_DLL_DEBUG_EVENT => {
+                let info = event.u.LoadDll();
+                DebugEventKind::LoadDll {
+                    file: file_handle(info.hFile),
+                    base_of_dll: info.lpBaseOfDll as usize,
+                }
+            }
+            UNLOAD_DLL_DEBUG_EVENT => DebugEventKind::UnloadDll {
+                base_of_dll: event.u.UnloadDll().lpBaseOfDll as usize,
+            },
+            OUTPUT_DEBUG_STRING_EVENT => {
+                let info = event.u.DebugString();
+                DebugEventKind::OutputDebugString {
+                    address: info.lpDebugStringData as usize,
+                    len: info.nDebugStringLength,
+                    unicode: info.fUnicode != 0,
+                }
+            }
+            RIP_EVENT => {
+                let info = event.u.RipInfo();
+                DebugEventKind::Rip {
+                    error: info.dwError,
+                    kind: info.dwType,
+                }
+            }
+            code => DebugEventKind::Unknown(code),
+        };
+
+        Self {
+            pid: event.dwProcessId,
+            tid: event.dwThreadId,
+            kind,
+        }
+    }
+}
+
+/// Take ownership of a file handle from a debug event, which may be null.
+unsafe fn file_handle(handle: HANDLE) -> Option<Handle> {
+    if handle.is_null() {
+        None
+    } else {
+        Some(Handle::from_raw(handle.cast()))
+    }
+}
+
+/// How a debuggee should continue after a [`DebugEvent`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ContinueStatus {
+    /// Continue the thread, marking any exception as handled.
+    Continue,
+
+    /// Continue the thread, marking an exception as not handled so the debuggee's exception handlers run.
+    ExceptionNotHandled,
+}
+
+impl From<ContinueStatus> for DWORD {
+    fn from(status: ContinueStatus) -> Self {
+        match status {
+            ContinueStatus::Continue => DBG_CONTINUE,
+            ContinueStatus::ExceptionNotHandled => DBG_EXCEPTION_NOT_HANDLED,
+        }
+    }
+}
+
+/// Wait for a debug event to occur in a process being debugged,
+/// until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
+///
+/// This must be called from the thread that attached to the debuggee.
+/// Every event must be followed by a call to [`continue_debug_event`].
+///
+/// # Errors
+/// Returns an error if the wait failed.
+/// Returns `Ok(None)` if the interval elapsed without an event.
+pub fn wait_for_debug_event(millis: u32) -> std::io::Result<Option<DebugEvent>> {
+    let mut event = MaybeUninit::<DEBUG_EVENT>::uninit();
+    let ret = unsafe { WaitForDebugEvent(event.as_mut_ptr(), millis) };
+
+    if ret == FALSE {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(ERROR_SEM_TIMEOUT as i32) {
+            return Ok(None);
+        }
+
+        return Err(err);
+    }
+
+    Ok(Some(unsafe { DebugEvent::from_raw(event.assume_init()) }))
+}
+
+/// Continue a thread that reported a debug event.
+///
+/// # Errors
+/// Returns an error if the thread could not be continued.
+pub fn continue_debug_event(pid: u32, tid: u32, status: ContinueStatus) -> std::io::Result<()> {
+    let ret = unsafe { ContinueDebugEvent(pid, tid, status.into()) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use winapi::um::debugapi::DebugActiveProcess;
+    use winapi::um::debugapi::DebugActiveProcessStop;
+
+    #[test]
+    fn debug_event_loop() {
+        let mut child = std::process::Command::new("ping")
+            .args(["-n", "30", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("failed to spawn child");
+        let pid = child.id();
+
+        if unsafe { DebugActiveProcess(pid) } == FALSE {
+            let err = std::io::Error::last_os_error();
+            child.kill().expect("failed to kill child");
+            child.wait().expect("failed to wait for child");
+
+            // Debugging may not be allowed in this environment.
+            assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+            return;
+        }
+
+        loop {
+            let event = wait_for_debug_event(10_000)
+                .expect("failed to wait for debug event")
+                .expect("timed out waiting for debug event");
+            continue_debug_event(event.pid, event.tid, ContinueStatus::Continue)
+                .expect("failed to continue");
+
+            if event.pid == pid {
+                if let DebugEventKind::CreateProcess { .. } = event.kind {
+                    break;
+                }
+            }
+        }
+
+        assert!(unsafe { DebugActiveProcessStop(pid) } != FALSE);
+
+        child.kill().expect("failed to kill child");
+        child.wait().expect("failed to wait for child");
+    }
+}
@@ -1,10 +1,51 @@
 use std::mem::ManuallyDrop;
 use std::os::windows::raw::HANDLE;
+#[cfg(feature = "winsock2")]
+use winapi::ctypes::c_int;
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::minwindef::TRUE;
+use winapi::shared::winerror::NO_ERROR;
+use winapi::shared::winerror::WAIT_TIMEOUT;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::errhandlingapi::SetLastError;
+use winapi::um::fileapi::CreateFileW;
+use winapi::um::fileapi::GetFileType;
+use winapi::um::fileapi::OPEN_EXISTING;
 use winapi::um::handleapi::CloseHandle;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::minwinbase::SECURITY_ATTRIBUTES;
+use winapi::um::namedpipeapi::CreatePipe;
+use winapi::um::synchapi::SignalObjectAndWait;
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::FILE_TYPE_CHAR;
+use winapi::um::winbase::FILE_TYPE_DISK;
+use winapi::um::winbase::FILE_TYPE_PIPE;
+use winapi::um::winbase::FILE_TYPE_REMOTE;
+use winapi::um::winbase::FILE_TYPE_UNKNOWN;
+use winapi::um::winbase::WAIT_ABANDONED;
+use winapi::um::winbase::WAIT_FAILED;
+use winapi::um::winbase::WAIT_OBJECT_0;
+use winapi::um::winnt::FILE_SHARE_READ;
+use winapi::um::winnt::FILE_SHARE_WRITE;
+use winapi::um::winnt::STATUS_USER_APC;
+#[cfg(feature = "winsock2")]
+use winapi::um::winsock2::getsockopt;
+#[cfg(feature = "winsock2")]
+use winapi::um::winsock2::WSAGetLastError;
+#[cfg(feature = "winsock2")]
+use winapi::um::winsock2::SOCKET_ERROR;
+#[cfg(feature = "winsock2")]
+use winapi::um::winsock2::SOCK_STREAM;
+#[cfg(feature = "winsock2")]
+use winapi::um::winsock2::SOL_SOCKET;
+#[cfg(feature = "winsock2")]
+use winapi::um::winsock2::SO_TYPE;
 
 // TODO: Consider allowing invalid handles.
 /// A wrapper around a winapi `HANDLE`.
 ///
+/// Kernel handles are valid in every thread of the process that owns them, so a [`Handle`] is `Send` and `Sync`.
+///
 #[repr(transparent)]
 #[derive(Debug)]
 pub struct Handle(HANDLE);
@@ -20,6 +61,37 @@ impl Handle {
         Self(handle)
     }
 
+    /// Open the `NUL` device with the given access rights, like `GENERIC_READ` or `GENERIC_WRITE`.
+    ///
+    /// Writes to the `NUL` device succeed and discard the data, while reads return EOF.
+    ///
+    /// # Errors
+    /// Returns an error if the `NUL` device could not be opened.
+    ///
+    pub fn null_device(access: u32) -> std::io::Result<Self> {
+        let path: Vec<u16> = r"\\.\NUL"
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let handle = unsafe {
+            CreateFileW(
+                path.as_ptr(),
+                access,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                std::ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(unsafe { Self::from_raw(handle.cast()) })
+    }
+
     /// Get the inner `HANDLE`.
     ///
     pub fn as_raw(&self) -> HANDLE {
@@ -32,6 +104,112 @@ impl Handle {
         ManuallyDrop::new(self).0
     }
 
+    /// Make a [`Handle`] from a [`std::fs::File`], taking ownership of its handle.
+    ///
+    /// Win32 calls on the [`Handle`] bypass any buffering done on top of the file, like with a [`std::io::BufWriter`],
+    /// so buffered data should be flushed before converting.
+    ///
+    pub fn from_file(file: std::fs::File) -> Self {
+        use std::os::windows::io::IntoRawHandle;
+
+        unsafe { Self::from_raw(file.into_raw_handle()) }
+    }
+
+    /// Convert this [`Handle`] into a [`std::fs::File`], transferring ownership of the handle.
+    ///
+    pub fn into_file(self) -> std::fs::File {
+        use std::os::windows::io::FromRawHandle;
+
+        unsafe { std::fs::File::from_raw_handle(self.into_raw()) }
+    }
+
+    /// Get the type of the file this [`Handle`] refers to.
+    ///
+    /// # Errors
+    /// Returns an error if the file type could not be retrieved.
+    ///
+    pub fn file_type(&self) -> std::io::Result<FileType> {
+        // `FILE_TYPE_UNKNOWN` is also returned on failure, so the last error is needed to tell the two apart.
+        unsafe { SetLastError(NO_ERROR) };
+        let file_type = unsafe { GetFileType(self.0.cast()) };
+
+        match file_type {
+            FILE_TYPE_DISK => Ok(FileType::Disk),
+            FILE_TYPE_CHAR => Ok(FileType::Char),
+            FILE_TYPE_PIPE => Ok(FileType::Pipe),
+            FILE_TYPE_REMOTE => Ok(FileType::Remote),
+            FILE_TYPE_UNKNOWN if unsafe { GetLastError() } != NO_ERROR => {
+                Err(std::io::Error::last_os_error())
+            }
+            _ => Ok(FileType::Unknown),
+        }
+    }
+
+    /// Convert this [`Handle`] into a [`std::net::TcpStream`], if it is a stream socket.
+    ///
+    /// Winsock must already be initialized, like by using any of the networking types in `std::net`.
+    ///
+    /// # Errors
+    /// Returns an error which contains this [`Handle`] if it is not a stream socket.
+    ///
+    #[cfg(feature = "winsock2")]
+    pub fn into_socket(self) -> Result<std::net::TcpStream, (Self, std::io::Error)> {
+        use std::os::windows::io::FromRawSocket;
+
+        // Sockets are reported as pipes, so this can quickly rule out other kinds of handles.
+        match self.file_type() {
+            Ok(FileType::Pipe) => {}
+            Ok(_) => {
+                return Err((
+                    self,
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "handle is not a socket"),
+                ));
+            }
+            Err(e) => return Err((self, e)),
+        }
+
+        let socket = self.0 as usize;
+        let mut socket_type: c_int = 0;
+        let mut socket_type_len = std::mem::size_of::<c_int>() as c_int;
+        let ret = unsafe {
+            getsockopt(
+                socket,
+                SOL_SOCKET,
+                SO_TYPE,
+                (&mut socket_type as *mut c_int).cast(),
+                &mut socket_type_len,
+            )
+        };
+
+        if ret == SOCKET_ERROR {
+            let code = unsafe { WSAGetLastError() };
+            return Err((self, std::io::Error::from_raw_os_error(code)));
+        }
+
+        if socket_type != SOCK_STREAM {
+            return Err((
+                self,
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "socket is not a stream socket",
+                ),
+            ));
+        }
+
+        Ok(unsafe { std::net::TcpStream::from_raw_socket(self.into_raw() as u64) })
+    }
+
+    /// Wait for the object this [`Handle`] refers to to be signaled,
+    /// until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
+    ///
+    /// # Errors
+    /// Returns an error if this [`Handle`] could not be waited on.
+    ///
+    pub fn wait(&self, millis: u32) -> std::io::Result<WaitResult> {
+        let ret = unsafe { WaitForSingleObject(self.0.cast(), millis) };
+        wait_result(ret)
+    }
+
     /// Try to close this [`Handle`].
     ///
     /// # Errors
@@ -57,3 +235,343 @@ impl Drop for Handle {
         std::mem::forget(Self(self.0).close());
     }
 }
+
+// Kernel handles are process-global and not tied to the thread that opened them.
+// The kernel synchronizes access to the objects they refer to, so sharing them across threads is fine.
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
+/// A wrapper type that owns a single [`Handle`], like a process or thread.
+///
+/// This provides the shared `close` implementation for these types.
+// This is only implemented by types behind other features.
+#[cfg_attr(
+    not(any(
+        feature = "processthreadsapi",
+        feature = "synchapi",
+        feature = "tlhelp32"
+    )),
+    allow(dead_code)
+)]
+pub(crate) trait OwnedHandle: Sized {
+    /// Make this type from the [`Handle`] it owns.
+    fn from_handle(handle: Handle) -> Self;
+
+    /// Get the [`Handle`] this type owns.
+    fn into_handle(self) -> Handle;
+
+    /// Try to close the [`Handle`] this type owns.
+    ///
+    /// # Errors
+    /// Returns an error which contains this object if this object could not be destroyed.
+    fn close(self) -> Result<(), (Self, std::io::Error)> {
+        self.into_handle()
+            .close()
+            .map_err(|(handle, err)| (Self::from_handle(handle), err))
+    }
+}
+
+/// The type of a file, as returned by [`Handle::file_type`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FileType {
+    /// A disk file
+    Disk,
+
+    /// A character file, like a console or printer
+    Char,
+
+    /// A pipe or socket
+    Pipe,
+
+    /// A remote file
+    Remote,
+
+    /// An unknown file type
+    Unknown,
+}
+
+/// The result of a successful wait on a [`Handle`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WaitResult {
+    /// The object was signaled.
+    Signaled,
+
+    /// The object is a mutex that was not released by its owning thread before it terminated.
+    Abandoned,
+
+    /// The wait was ended by a user-mode APC or an I/O completion routine queued to the thread.
+    IoCompletion,
+
+    /// The timeout elapsed before the object was signaled.
+    Timeout,
+}
+
+/// Signal `to_signal` and wait on `to_wait` as a single operation,
+/// until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
+///
+/// `to_signal` may be a semaphore, mutex, or event.
+/// If `alertable` is true, the wait may be ended early by an APC or I/O completion routine.
+///
+/// # Errors
+/// Returns an error if `to_signal` could not be signaled or `to_wait` could not be waited on.
+///
+pub fn signal_object_and_wait(
+    to_signal: &Handle,
+    to_wait: &Handle,
+    millis: u32,
+    alertable: bool,
+) -> std::io::Result<WaitResult> {
+    let alertable = if alertable { TRUE } else { FALSE };
+    let ret = unsafe {
+        SignalObjectAndWait(
+            to_signal.as_raw().cast(),
+            to_wait.as_raw().cast(),
+            millis,
+            alertable,
+        )
+    };
+
+    wait_result(ret)
+}
+
+/// Convert the return value of a wait function into a [`WaitResult`].
+fn wait_result(ret: u32) -> std::io::Result<WaitResult> {
+    match ret {
+        WAIT_OBJECT_0 => Ok(WaitResult::Signaled),
+        WAIT_ABANDONED => Ok(WaitResult::Abandoned),
+        STATUS_USER_APC => Ok(WaitResult::IoCompletion),
+        WAIT_TIMEOUT => Ok(WaitResult::Timeout),
+        WAIT_FAILED => Err(std::io::Error::last_os_error()),
+        ret => Err(std::io::Error::other(format!(
+            "unknown wait result `{}`",
+            ret
+        ))),
+    }
+}
+
+/// Create an anonymous pipe, returning the `(read, write)` handles.
+///
+/// The handles are not inheritable.
+/// Use [`create_pipe_with_inherit`] to make handles that can be passed to a child process.
+///
+/// # Errors
+/// Returns an error if the pipe could not be created.
+///
+pub fn create_pipe() -> std::io::Result<(Handle, Handle)> {
+    create_pipe_with_inherit(false)
+}
+
+/// Create an anonymous pipe, returning the `(read, write)` handles.
+///
+/// If `inheritable` is true, both handles may be inherited by child processes made with `CreateProcessW`.
+///
+/// # Errors
+/// Returns an error if the pipe could not be created.
+///
+pub fn create_pipe_with_inherit(inheritable: bool) -> std::io::Result<(Handle, Handle)> {
+    let mut security_attributes = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: std::ptr::null_mut(),
+        bInheritHandle: if inheritable { TRUE } else { FALSE },
+    };
+
+    let mut read = std::ptr::null_mut();
+    let mut write = std::ptr::null_mut();
+    let ret = unsafe { CreatePipe(&mut read, &mut write, &mut security_attributes, 0) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(unsafe {
+        (
+            Handle::from_raw(read.cast()),
+            Handle::from_raw(write.cast()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+    use std::io::Write;
+    use std::os::windows::io::FromRawHandle;
+    use winapi::um::synchapi::CreateEventW;
+    use winapi::um::winnt::GENERIC_READ;
+    use winapi::um::winnt::GENERIC_WRITE;
+
+    #[test]
+    fn null_device_write() {
+        let handle = Handle::null_device(GENERIC_WRITE).expect("failed to open NUL");
+        let mut file = unsafe { std::fs::File::from_raw_handle(handle.into_raw()) };
+        file.write_all(b"Hello World!")
+            .expect("failed to write to NUL");
+    }
+
+    #[test]
+    fn null_device_read() {
+        let handle = Handle::null_device(GENERIC_READ).expect("failed to open NUL");
+        let mut file = unsafe { std::fs::File::from_raw_handle(handle.into_raw()) };
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)
+            .expect("failed to read from NUL");
+        assert!(buffer.is_empty());
+    }
+
+    /// Create an auto-reset event.
+    fn create_event(signaled: bool) -> Handle {
+        let signaled = if signaled { TRUE } else { FALSE };
+        let handle =
+            unsafe { CreateEventW(std::ptr::null_mut(), FALSE, signaled, std::ptr::null()) };
+        assert!(!handle.is_null(), "failed to create event");
+        unsafe { Handle::from_raw(handle.cast()) }
+    }
+
+    #[test]
+    fn signal_object_and_wait_works() {
+        let to_signal = create_event(false);
+        let to_wait = create_event(true);
+
+        let result = signal_object_and_wait(&to_signal, &to_wait, 0, false)
+            .expect("failed to signal and wait");
+        assert_eq!(result, WaitResult::Signaled);
+
+        let result = to_signal.wait(0).expect("failed to wait");
+        assert_eq!(result, WaitResult::Signaled);
+
+        // `to_wait` is auto-reset, so it was reset by the wait.
+        let result = signal_object_and_wait(&to_signal, &to_wait, 0, false)
+            .expect("failed to signal and wait");
+        assert_eq!(result, WaitResult::Timeout);
+    }
+
+    #[test]
+    #[cfg(feature = "winsock2")]
+    fn into_socket() {
+        use std::os::windows::io::IntoRawSocket;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap())
+            .expect("failed to connect");
+        let (mut server, _) = listener.accept().expect("failed to accept");
+
+        let handle = unsafe { Handle::from_raw(client.into_raw_socket() as HANDLE) };
+        let mut client = handle
+            .into_socket()
+            .map_err(|(_, e)| e)
+            .expect("failed to convert handle into socket");
+
+        client.write_all(b"ping").expect("failed to write");
+        let mut buffer = [0; 4];
+        server.read_exact(&mut buffer).expect("failed to read");
+        assert_eq!(&buffer, b"ping");
+
+        server.write_all(b"pong").expect("failed to write");
+        client.read_exact(&mut buffer).expect("failed to read");
+        assert_eq!(&buffer, b"pong");
+
+        let handle = Handle::null_device(GENERIC_READ).expect("failed to open NUL");
+        assert!(handle.into_socket().is_err());
+    }
+
+    #[test]
+    fn file_type_disk() {
+        use std::os::windows::io::AsRawHandle;
+
+        let file = std::fs::File::open(std::env::current_exe().unwrap()).expect("failed to open");
+        let handle = ManuallyDrop::new(unsafe { Handle::from_raw(file.as_raw_handle()) });
+        assert_eq!(
+            handle.file_type().expect("failed to get file type"),
+            FileType::Disk
+        );
+    }
+
+    #[test]
+    fn file_type_stdout() {
+        use std::os::windows::io::AsRawHandle;
+
+        let stdout = std::io::stdout();
+        let raw = stdout.as_raw_handle();
+        if raw.is_null() {
+            // There is no stdout to check.
+            return;
+        }
+
+        let handle = ManuallyDrop::new(unsafe { Handle::from_raw(raw) });
+        let file_type = handle.file_type().expect("failed to get file type");
+        // stdout is usually a console or a pipe, but may be redirected to a file.
+        assert!(
+            matches!(file_type, FileType::Char | FileType::Pipe | FileType::Disk),
+            "{:?}",
+            file_type
+        );
+    }
+
+    #[test]
+    fn create_pipe_round_trip() {
+        let (read, write) = create_pipe().expect("failed to create pipe");
+        assert_eq!(
+            read.file_type().expect("failed to get file type"),
+            FileType::Pipe
+        );
+
+        let mut write = unsafe { std::fs::File::from_raw_handle(write.into_raw()) };
+        write.write_all(b"Hello World!").expect("failed to write");
+        drop(write);
+
+        let mut read = unsafe { std::fs::File::from_raw_handle(read.into_raw()) };
+        let mut buffer = Vec::new();
+        read.read_to_end(&mut buffer).expect("failed to read");
+        assert_eq!(buffer, b"Hello World!");
+    }
+
+    #[test]
+    fn create_pipe_inheritable() {
+        use winapi::um::handleapi::GetHandleInformation;
+        use winapi::um::winbase::HANDLE_FLAG_INHERIT;
+
+        for &inheritable in &[true, false] {
+            let (read, write) =
+                create_pipe_with_inherit(inheritable).expect("failed to create pipe");
+            for handle in &[read, write] {
+                let mut flags = 0;
+                let ret = unsafe { GetHandleInformation(handle.as_raw().cast(), &mut flags) };
+                assert_ne!(ret, FALSE, "failed to get handle info");
+                assert_eq!(flags & HANDLE_FLAG_INHERIT != 0, inheritable);
+            }
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "skylight-handle-file-round-trip-{}.txt",
+            std::process::id()
+        ));
+        let file = std::fs::File::create(&path).expect("failed to create file");
+
+        let handle = Handle::from_file(file);
+        assert_eq!(
+            handle.file_type().expect("failed to get file type"),
+            FileType::Disk
+        );
+
+        let mut file = handle.into_file();
+        file.write_all(b"Hello World!").expect("failed to write");
+        drop(file);
+
+        let data = std::fs::read(&path).expect("failed to read file");
+        std::fs::remove_file(&path).expect("failed to remove file");
+        assert_eq!(data, b"Hello World!");
+    }
+
+    #[test]
+    fn handle_is_send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+
+        assert_send::<Handle>();
+        assert_sync::<Handle>();
+    }
+}
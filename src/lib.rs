@@ -1,3 +1,9 @@
+/// debugapi.h Utilities
+#[cfg(feature = "debugapi")]
+pub mod debugapi;
+#[cfg(feature = "debugapi")]
+pub use self::debugapi::*;
+
 /// handleapi.h Utilities
 #[cfg(feature = "handleapi")]
 pub mod handleapi;
@@ -34,6 +40,12 @@ pub mod shlobj;
 #[cfg(feature = "shlobj")]
 pub use self::shlobj::*;
 
+/// synchapi.h Utilities
+#[cfg(feature = "synchapi")]
+pub mod synchapi;
+#[cfg(feature = "synchapi")]
+pub use self::synchapi::*;
+
 /// tlhelp32.h Utilities
 #[cfg(feature = "tlhelp32")]
 pub mod tlhelp32;
@@ -46,6 +58,12 @@ pub mod winbase;
 #[cfg(feature = "winbase")]
 pub use self::winbase::*;
 
+/// wincon.h Utilities
+#[cfg(feature = "wincon")]
+pub mod wincon;
+#[cfg(feature = "wincon")]
+pub use self::wincon::*;
+
 /// wincrypt.h Utilities
 #[cfg(feature = "wincrypt")]
 pub mod wincrypt;
@@ -1,9 +1,18 @@
+use std::ffi::c_void;
+use std::ffi::CStr;
 use std::ffi::OsStr;
 use std::mem::ManuallyDrop;
 use std::os::windows::ffi::OsStrExt;
+use std::ptr::NonNull;
+use winapi::shared::minwindef::FARPROC;
 use winapi::shared::minwindef::HMODULE;
+use winapi::shared::ntdef::LPCSTR;
 use winapi::um::libloaderapi::FreeLibrary;
+use winapi::um::libloaderapi::GetModuleHandleExW;
+use winapi::um::libloaderapi::GetProcAddress;
 use winapi::um::libloaderapi::LoadLibraryW;
+use winapi::um::libloaderapi::GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS;
+use winapi::um::libloaderapi::GET_MODULE_HANDLE_EX_FLAG_PIN;
 
 /// A dynamically loaded library
 pub struct HModule(HMODULE);
@@ -22,11 +31,77 @@ impl HModule {
         Ok(Self(hmodule))
     }
 
+    /// Get a library that is already loaded in this process by name.
+    ///
+    /// Unlike [`HModule::load`], this will not load the library if it is not already loaded.
+    /// The reference count of the library is incremented, so it is freed when the returned [`HModule`] is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the library is not loaded in this process.
+    pub fn get_module_handle(lib: &OsStr) -> std::io::Result<Self> {
+        let lib = lib.encode_wide().chain(Some(0)).collect::<Vec<_>>();
+        let mut hmodule = std::ptr::null_mut();
+        let ret = unsafe { GetModuleHandleExW(0, lib.as_ptr(), &mut hmodule) };
+        if ret == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self(hmodule))
+    }
+
+    /// Pin this library, keeping it loaded until the process exits.
+    ///
+    /// This intentionally leaks the library.
+    /// Dropping this [`HModule`] or calling `FreeLibrary` will no longer unload it,
+    /// so pointers from [`HModule::get_proc_address`] stay valid for the lifetime of the process.
+    ///
+    /// # Errors
+    /// Returns an error if the library could not be pinned.
+    pub fn pin(&self) -> std::io::Result<()> {
+        let mut hmodule = std::ptr::null_mut();
+        // With `GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS`, the module name is an address in the module.
+        // A `HMODULE` is the base address of the module, so it can be used here.
+        let ret = unsafe {
+            GetModuleHandleExW(
+                GET_MODULE_HANDLE_EX_FLAG_PIN | GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+                self.0.cast(),
+                &mut hmodule,
+            )
+        };
+        if ret == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
     /// Get the raw HMODULE
     pub fn as_raw(&self) -> HMODULE {
         self.0
     }
 
+    /// Get the address of an exported function or variable from this library.
+    ///
+    /// The result must be cast to the correct type before it is used.
+    ///
+    /// # Errors
+    /// Returns an error if the export could not be found.
+    pub fn get_proc_address(&self, name: &CStr) -> std::io::Result<NonNull<c_void>> {
+        let ptr = unsafe { GetProcAddress(self.0, name.as_ptr()) };
+        NonNull::new(ptr.cast()).ok_or_else(std::io::Error::last_os_error)
+    }
+
+    /// Get the address of a function exported by ordinal from this library.
+    ///
+    /// The result must be cast to the correct type before it is used.
+    /// Returns `None` if the export could not be found.
+    pub fn get_proc_address_by_ordinal(&self, ordinal: u16) -> Option<FARPROC> {
+        // Like the `MAKEINTRESOURCEA` macro, the ordinal is passed in the low word of the name pointer.
+        let ptr = unsafe { GetProcAddress(self.0, usize::from(ordinal) as LPCSTR) };
+        if ptr.is_null() {
+            return None;
+        }
+        Some(ptr)
+    }
+
     /// Destroy this object.
     pub fn destroy(self) -> Result<(), (Self, std::io::Error)> {
         let lib = ManuallyDrop::new(self);
@@ -51,3 +126,45 @@ impl Drop for HModule {
         std::mem::forget(Self(self.0).destroy());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn get_module_handle_kernel32() {
+        let kernel32 =
+            HModule::get_module_handle("kernel32.dll".as_ref()).expect("kernel32 is not loaded");
+        assert!(!kernel32.as_raw().is_null());
+    }
+
+    #[test]
+    fn get_module_handle_not_loaded() {
+        assert!(HModule::get_module_handle("skylight_not_a_real_library.dll".as_ref()).is_err());
+    }
+
+    #[test]
+    fn pin() {
+        let lib = unsafe { HModule::load("imagehlp.dll".as_ref()) }.expect("failed to load");
+        lib.pin().expect("failed to pin");
+        drop(lib);
+
+        HModule::get_module_handle("imagehlp.dll".as_ref()).expect("pinned library was unloaded");
+    }
+
+    #[test]
+    fn get_proc_address_by_ordinal() {
+        // Ordinals are usually version-specific, but `ws2_32.dll` keeps the ordinals of the original Winsock functions for compatibility.
+        // Ordinal 115 is `WSAStartup`.
+        let ws2_32 = unsafe { HModule::load("ws2_32.dll".as_ref()) }.expect("failed to load");
+        let by_ordinal = ws2_32
+            .get_proc_address_by_ordinal(115)
+            .expect("failed to get WSAStartup by ordinal");
+        let by_name = ws2_32
+            .get_proc_address(CStr::from_bytes_with_nul(b"WSAStartup\0").unwrap())
+            .expect("failed to get WSAStartup by name");
+        assert_eq!(by_ordinal as usize, by_name.as_ptr() as usize);
+
+        assert!(ws2_32.get_proc_address_by_ordinal(u16::MAX).is_none());
+    }
+}
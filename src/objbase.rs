@@ -6,12 +6,16 @@ use std::os::windows::ffi::OsStrExt;
 use std::os::windows::ffi::OsStringExt;
 use std::ptr::NonNull;
 use winapi::shared::guiddef::CLSID;
+use winapi::shared::guiddef::GUID;
 use winapi::shared::minwindef::DWORD;
 use winapi::shared::winerror::FAILED;
+use winapi::um::combaseapi::CLSIDFromString;
+use winapi::um::combaseapi::CoCreateGuid;
 use winapi::um::combaseapi::CoCreateInstance;
 use winapi::um::combaseapi::CoIncrementMTAUsage;
 use winapi::um::combaseapi::CoTaskMemAlloc;
 use winapi::um::combaseapi::CoTaskMemFree;
+use winapi::um::combaseapi::StringFromGUID2;
 use winapi::Interface;
 
 // TODO: Consider returning cookie
@@ -55,8 +59,80 @@ pub unsafe fn create_instance<T: Interface>(
     Ok(instance.cast())
 }
 
+/// Make a new, unique [`GUID`].
+///
+/// # Errors
+/// Returns an error if a [`GUID`] could not be created.
+pub fn create_guid() -> std::io::Result<GUID> {
+    let mut guid = GUID {
+        Data1: 0,
+        Data2: 0,
+        Data3: 0,
+        Data4: [0; 8],
+    };
+    let code = unsafe { CoCreateGuid(&mut guid) };
+
+    if FAILED(code) {
+        return Err(HResult::from(code).into());
+    }
+
+    Ok(guid)
+}
+
+/// Format a [`GUID`] in the braced form, like `{6B29FC40-CA47-1067-B31D-00DD010662DA}`.
+pub fn format_guid(guid: &GUID) -> String {
+    // 38 chars for the braced form, +1 for the NUL terminator.
+    let mut buffer = [0u16; 39];
+    let len = unsafe { StringFromGUID2(guid, buffer.as_mut_ptr(), buffer.len() as i32) };
+    assert!(len > 0, "the guid buffer is too small");
+
+    // -1 for the NUL terminator.
+    String::from_utf16_lossy(&buffer[..(len - 1) as usize])
+}
+
+/// Parse a [`GUID`] from the braced form, like `{6B29FC40-CA47-1067-B31D-00DD010662DA}`.
+///
+/// This also accepts ProgIDs registered on this system, which are looked up in the registry.
+///
+/// # Errors
+/// Returns an error if the string is not a valid [`GUID`] or registered ProgID.
+pub fn parse_guid(s: &str) -> Result<GUID, HResult> {
+    let s: Vec<u16> = s.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut guid = GUID {
+        Data1: 0,
+        Data2: 0,
+        Data3: 0,
+        Data4: [0; 8],
+    };
+    let code = unsafe { CLSIDFromString(s.as_ptr(), &mut guid) };
+
+    if FAILED(code) {
+        return Err(HResult::from(code));
+    }
+
+    Ok(guid)
+}
+
+/// Split a double-NUL-terminated list of wide strings, like `a\0b\0\0`, into its elements.
+///
+/// The list ends at the first empty element, or at the end of the buffer if it is not terminated.
+/// An empty buffer, or one that starts with a NUL, is an empty list.
+pub fn split_double_nul_wide(buf: &[u16]) -> Vec<OsString> {
+    buf.split(|c| *c == 0)
+        .take_while(|element| !element.is_empty())
+        .map(OsString::from_wide)
+        .collect()
+}
+
 /// A Wide String allocated with CoTaskMemAlloc.
-pub struct CoTaskMemWideString(NonNull<u16>);
+///
+/// Most strings are NUL-terminated, so methods like [`CoTaskMemWideString::iter`] and [`CoTaskMemWideString::as_slice`] stop at the first NUL.
+/// Some APIs instead return a double-NUL-terminated list, like `a\0b\0\0`, where each element is NUL-terminated and the list ends with an empty element.
+/// Use [`CoTaskMemWideString::from_raw_double_nul`] and [`CoTaskMemWideString::iter_until_double_nul`] to read every element of such a list.
+pub struct CoTaskMemWideString {
+    ptr: NonNull<u16>,
+    double_nul: bool,
+}
 
 impl CoTaskMemWideString {
     /// Allocate a new string.
@@ -80,7 +156,45 @@ impl CoTaskMemWideString {
             }
         }
 
-        Some(Self(ptr))
+        Some(unsafe { Self::from_raw(ptr) })
+    }
+
+    /// Allocate a new string from a wide char slice, appending a NUL terminator.
+    ///
+    /// The slice is copied as-is, so an interior NUL will end the string as seen by [`CoTaskMemWideString::iter`],
+    /// [`CoTaskMemWideString::as_slice`], and other methods that stop at the first NUL.
+    ///
+    /// # Errors
+    /// * Returns `None` if the memory could not be allocated.
+    pub fn from_wide_slice(data: &[u16]) -> Option<Self> {
+        // +1 for NUL terminator
+        let len = data.len().checked_add(1)?;
+
+        // x2 since wide chars have twice the bytes
+        let ptr = unsafe { CoTaskMemAlloc(len.checked_mul(2)?) };
+
+        // Early return on allocation failure
+        let ptr: NonNull<u16> = NonNull::new(ptr.cast())?;
+
+        // Copy data + nul terminator
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr.as_ptr(), data.len());
+            std::ptr::write(ptr.as_ptr().add(data.len()), 0);
+        }
+
+        Some(unsafe { Self::from_raw(ptr) })
+    }
+
+    /// Allocate a new string from a [`str`].
+    ///
+    /// Like [`CoTaskMemWideString::from_wide_slice`], an interior NUL will end the string.
+    ///
+    /// # Errors
+    /// * Returns `None` if the memory could not be allocated.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(data: &str) -> Option<Self> {
+        let data: Vec<u16> = data.encode_utf16().collect();
+        Self::from_wide_slice(&data)
     }
 
     /// Make a new [`CoTaskMemWideString`] from a non-null u16 ptr.
@@ -89,7 +203,25 @@ impl CoTaskMemWideString {
     /// * `ptr` must be a valid nul-terminated widestring
     /// * `ptr` must be allocated with CoTaskMemAlloc.
     pub unsafe fn from_raw(ptr: NonNull<u16>) -> Self {
-        Self(ptr)
+        Self {
+            ptr,
+            double_nul: false,
+        }
+    }
+
+    /// Make a new [`CoTaskMemWideString`] from a non-null u16 ptr to a double-NUL-terminated list.
+    ///
+    /// Methods that stop at the first NUL will only see the first element.
+    /// Use [`CoTaskMemWideString::iter_until_double_nul`] to get every element.
+    ///
+    /// # Safety
+    /// * `ptr` must be a valid double-nul-terminated widestring
+    /// * `ptr` must be allocated with CoTaskMemAlloc.
+    pub unsafe fn from_raw_double_nul(ptr: NonNull<u16>) -> Self {
+        Self {
+            ptr,
+            double_nul: true,
+        }
     }
 
     /// Get the length of the string.
@@ -113,6 +245,38 @@ impl CoTaskMemWideString {
         Iter::new(self)
     }
 
+    /// Iterate over the elements of a double-NUL-terminated list.
+    ///
+    /// If this string was not made with [`CoTaskMemWideString::from_raw_double_nul`],
+    /// it is treated as a list with a single element.
+    /// Empty strings and lists yield no elements.
+    pub fn iter_until_double_nul(&self) -> impl Iterator<Item = OsString> {
+        let len = if self.double_nul {
+            let mut len = 0;
+            loop {
+                // # Safety
+                // `len` is always the start of an element, and every element is NUL-terminated.
+                let element_len = unsafe {
+                    let start = self.ptr.as_ptr().add(len);
+                    (0..).take_while(|&i| *start.add(i) != 0).count()
+                };
+
+                if element_len == 0 {
+                    break len;
+                }
+
+                len += element_len + 1;
+            }
+        } else {
+            self.len()
+        };
+
+        // # Safety
+        // `len` only covers the elements of the list and their NUL terminators.
+        let list = unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), len) };
+        split_double_nul_wide(list).into_iter()
+    }
+
     /// Get this as an [`OsString`].
     ///
     /// This does not include the NUL terminator. This is O(n).
@@ -124,7 +288,7 @@ impl CoTaskMemWideString {
     ///
     /// This does not include the NUL terminator. This is O(n).
     pub fn as_slice(&self) -> &[u16] {
-        let ptr = self.0.as_ptr();
+        let ptr = self.ptr.as_ptr();
         let len = self.len();
 
         unsafe { std::slice::from_raw_parts(ptr, len) }
@@ -151,7 +315,7 @@ impl std::fmt::Debug for CoTaskMemWideString {
 impl Drop for CoTaskMemWideString {
     fn drop(&mut self) {
         unsafe {
-            CoTaskMemFree(self.0.as_ptr().cast());
+            CoTaskMemFree(self.ptr.as_ptr().cast());
         }
     }
 }
@@ -169,7 +333,7 @@ impl<'a> Iter<'a> {
 
     /// Get the current wide char.
     pub fn current(&self) -> u16 {
-        unsafe { std::ptr::read(self.data.0.as_ptr().offset(self.offset)) }
+        unsafe { std::ptr::read(self.data.ptr.as_ptr().offset(self.offset)) }
     }
 
     /// Check if the current wide char is nul.
@@ -216,4 +380,85 @@ mod test {
             assert!(empty_str.is_empty());
         }
     }
+
+    #[test]
+    fn guid_round_trip() {
+        let guid = create_guid().expect("failed to create guid");
+        let formatted = format_guid(&guid);
+        assert_eq!(formatted.len(), 38);
+        assert!(formatted.starts_with('{') && formatted.ends_with('}'));
+
+        let parsed = parse_guid(&formatted).expect("failed to parse guid");
+        assert!(winapi::shared::guiddef::IsEqualGUID(&guid, &parsed));
+        assert_eq!(format_guid(&parsed), formatted);
+
+        assert!(parse_guid("not a guid").is_err());
+    }
+
+    #[test]
+    fn co_task_mem_wide_string_from_wide_slice() {
+        let data: Vec<u16> = "hello".encode_utf16().collect();
+        let s = CoTaskMemWideString::from_wide_slice(&data).expect("failed to allocate");
+        assert_eq!(s.as_slice(), data.as_slice());
+
+        let s = CoTaskMemWideString::from_wide_slice(&[]).expect("failed to allocate");
+        assert!(s.is_empty());
+
+        // An interior NUL ends the string.
+        let data: Vec<u16> = "hello\0world".encode_utf16().collect();
+        let s = CoTaskMemWideString::from_wide_slice(&data).expect("failed to allocate");
+        assert_eq!(s.as_os_string(), "hello");
+    }
+
+    #[test]
+    fn co_task_mem_wide_string_from_str() {
+        let s = CoTaskMemWideString::from_str("hello world!").expect("failed to allocate");
+        assert_eq!(s.as_os_string(), "hello world!");
+    }
+
+    #[test]
+    fn co_task_mem_wide_string_iter_until_double_nul() {
+        // `from_wide_slice` appends the final NUL, making "a\0b\0\0".
+        let data: Vec<u16> = "a\0b\0".encode_utf16().collect();
+        let s = CoTaskMemWideString::from_wide_slice(&data).expect("failed to allocate");
+        let s =
+            unsafe { CoTaskMemWideString::from_raw_double_nul(std::mem::ManuallyDrop::new(s).ptr) };
+
+        let elements: Vec<OsString> = s.iter_until_double_nul().collect();
+        assert_eq!(elements, ["a", "b"]);
+
+        // Methods that stop at the first NUL only see the first element.
+        assert_eq!(s.as_os_string(), "a");
+
+        // Single-NUL-terminated strings are a list with one element.
+        let s = CoTaskMemWideString::from_str("a").expect("failed to allocate");
+        let elements: Vec<OsString> = s.iter_until_double_nul().collect();
+        assert_eq!(elements, ["a"]);
+
+        let s = CoTaskMemWideString::from_str("").expect("failed to allocate");
+        assert_eq!(s.iter_until_double_nul().count(), 0);
+    }
+
+    #[test]
+    fn split_double_nul_wide_two_paths() {
+        let buf: Vec<u16> = "C:\\a.txt\0C:\\b.txt\0\0".encode_utf16().collect();
+        assert_eq!(split_double_nul_wide(&buf), ["C:\\a.txt", "C:\\b.txt"]);
+    }
+
+    #[test]
+    fn split_double_nul_wide_single_path() {
+        let buf: Vec<u16> = "C:\\a.txt\0\0".encode_utf16().collect();
+        assert_eq!(split_double_nul_wide(&buf), ["C:\\a.txt"]);
+
+        // A missing terminator still yields the last element.
+        let buf: Vec<u16> = "C:\\a.txt".encode_utf16().collect();
+        assert_eq!(split_double_nul_wide(&buf), ["C:\\a.txt"]);
+    }
+
+    #[test]
+    fn split_double_nul_wide_empty() {
+        assert!(split_double_nul_wide(&[]).is_empty());
+        assert!(split_double_nul_wide(&[0]).is_empty());
+        assert!(split_double_nul_wide(&[0, 0]).is_empty());
+    }
 }
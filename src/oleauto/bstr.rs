@@ -9,14 +9,26 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::mem::ManuallyDrop;
 use std::num::TryFromIntError;
+use std::ops::Add;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::ops::Range;
 use std::os::windows::ffi::OsStrExt;
 use std::os::windows::ffi::OsStringExt;
 use std::str::FromStr;
+use winapi::ctypes::c_int;
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::minwindef::TRUE;
 use winapi::shared::wtypes::BSTR;
 use winapi::um::oleauto::SysAllocStringLen;
 use winapi::um::oleauto::SysFreeString;
+use winapi::um::oleauto::SysReAllocStringLen;
+use winapi::um::stringapiset::CompareStringOrdinal;
+
+// The `CompareString*` return values are not exposed by `winapi`.
+const CSTR_LESS_THAN: c_int = 1;
+const CSTR_EQUAL: c_int = 2;
+const CSTR_GREATER_THAN: c_int = 3;
 
 /// An Error that may occur while creating a [`BStr`].
 #[derive(Debug, PartialEq)]
@@ -54,6 +66,83 @@ impl std::error::Error for BStrCreationError {
     }
 }
 
+/// Sum the lengths of multiple wide char buffers into the length of a single [`BStr`].
+///
+/// All methods that compute the length of a new [`BStr`] should use this.
+///
+/// # Errors
+/// Returns [`BStrCreationError::LenTooLarge`] if the sum overflows a [`usize`] or cannot fit in a [`u32`].
+///
+fn checked_wide_len(parts: impl Iterator<Item = usize>) -> Result<u32, BStrCreationError> {
+    // A `usize` overflow is also too large for a `u32`,
+    // so saturating still fails the conversion below.
+    let len = parts.fold(0_usize, |len, part| len.saturating_add(part));
+
+    u32::try_from(len).map_err(BStrCreationError::LenTooLarge)
+}
+
+/// An Error that may occur while creating a [`BStr`] from an iterator of fallible chars.
+#[derive(Debug, PartialEq)]
+pub enum TryFromCharError<E> {
+    /// The iterator yielded an error.
+    Iter(E),
+
+    /// Failed to create the [`BStr`].
+    Creation(BStrCreationError),
+}
+
+impl<E> std::fmt::Display for TryFromCharError<E>
+where
+    E: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::Iter(e) => write!(f, "the iterator yielded an error ({})", e),
+            Self::Creation(e) => write!(f, "failed to create a bstr ({})", e),
+        }
+    }
+}
+
+impl<E> std::error::Error for TryFromCharError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self {
+            Self::Iter(e) => Some(e),
+            Self::Creation(e) => Some(e),
+        }
+    }
+}
+
+/// An Error that may occur while creating a [`BStr`] with [`BStr::from_os_str_checked`].
+#[derive(Debug, PartialEq)]
+pub enum BStrFromOsStrError {
+    /// The string contains an unpaired surrogate, so it is not valid UTF16.
+    LoneSurrogate(std::char::DecodeUtf16Error),
+
+    /// Failed to create the [`BStr`].
+    Creation(BStrCreationError),
+}
+
+impl std::fmt::Display for BStrFromOsStrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::LoneSurrogate(e) => write!(f, "the string is not valid utf16 ({})", e),
+            Self::Creation(e) => write!(f, "failed to create a bstr ({})", e),
+        }
+    }
+}
+
+impl std::error::Error for BStrFromOsStrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self {
+            Self::LoneSurrogate(e) => Some(e),
+            Self::Creation(e) => Some(e),
+        }
+    }
+}
+
 /// A BStr allocated using `SysAllocString` or similar.
 /// This type may or may not contain valid UTF16.
 ///
@@ -109,7 +198,7 @@ impl BStr {
         iter: impl Iterator<Item = u16>,
         len: usize,
     ) -> Result<Self, BStrCreationError> {
-        let len_u32 = len.try_into().map_err(BStrCreationError::LenTooLarge)?;
+        let len_u32 = checked_wide_len(std::iter::once(len))?;
         let ptr = unsafe { SysAllocStringLen(std::ptr::null_mut(), len_u32) };
 
         if ptr.is_null() {
@@ -140,16 +229,41 @@ impl BStr {
         Ok(Self(ptr))
     }
 
+    /// Try to make a new [`BStr`] from a wide char iterator with an exact size.
+    ///
+    /// The iterator's [`ExactSizeIterator::len`] is used as the length, so it is only walked once.
+    ///
+    /// # Errors
+    /// Returns a [`BStrCreationError`] if a new [`BStr`] could not be allocated, if the length cannot be stored in a [`u32`],
+    /// or if the number of items in the iterator does not match the reported length.
+    ///
+    pub fn from_wide_iter_exact(
+        iter: impl ExactSizeIterator<Item = u16>,
+    ) -> Result<Self, BStrCreationError> {
+        let len = iter.len();
+        Self::from_wide_iter(iter, len)
+    }
+
     /// Try to make a new [`BStr`] from a wide char slice.
     ///
     /// # Errors
     /// Returns a `BStrCreationError` if a new [`BStr`] could not be allocated or if the length cannot be stored in a [`u32`].
     ///
     pub fn from_wide_slice(slice: &[u16]) -> Result<Self, BStrCreationError> {
-        let len = slice
-            .len()
-            .try_into()
-            .map_err(BStrCreationError::LenTooLarge)?;
+        Self::from_wide_slice_exact(slice)
+    }
+
+    /// Try to make a new [`BStr`] from a wide char slice, trusting the slice length as the length of the new [`BStr`].
+    ///
+    /// The data is copied with a single `SysAllocStringLen` call, without counting or iterating over the data first.
+    /// This is the cheapest way to make a [`BStr`] from wide data that is already owned.
+    /// Note that a [`BStrRef`] cannot borrow an arbitrary `&[u16]`, as a `BSTR` must be prefixed with its length.
+    ///
+    /// # Errors
+    /// Returns a `BStrCreationError` if a new [`BStr`] could not be allocated or if the length cannot be stored in a [`u32`].
+    ///
+    pub fn from_wide_slice_exact(slice: &[u16]) -> Result<Self, BStrCreationError> {
+        let len = checked_wide_len(std::iter::once(slice.len()))?;
 
         let ptr = unsafe { SysAllocStringLen(slice.as_ptr(), len) };
 
@@ -160,8 +274,94 @@ impl BStr {
         }
     }
 
+    /// Try to make a new [`BStr`] by letting `f` fill a buffer of `capacity` wide chars.
+    ///
+    /// `f` is called with a ptr to the zeroed buffer and its capacity, and returns the number of wide chars it used.
+    /// The [`BStr`] is then shrunk to that length.
+    /// This is useful for C-style APIs that fill a caller-provided buffer and report the used length.
+    ///
+    /// # Errors
+    /// Returns a `BStrCreationError` if a new [`BStr`] could not be allocated or if the capacity cannot be stored in a [`u32`].
+    ///
+    /// # Panics
+    /// Panics if `f` returns a length larger than `capacity` or if the [`BStr`] could not be shrunk.
+    ///
+    pub fn build_with<F>(capacity: usize, f: F) -> Result<Self, BStrCreationError>
+    where
+        F: FnOnce(*mut u16, usize) -> usize,
+    {
+        let capacity_u32 = checked_wide_len(std::iter::once(capacity))?;
+        let ptr = unsafe { SysAllocStringLen(std::ptr::null_mut(), capacity_u32) };
+
+        if ptr.is_null() {
+            return Err(BStrCreationError::AllocFailed);
+        }
+
+        // Zero the buffer so that `f` cannot expose uninitialized memory.
+        unsafe {
+            std::ptr::write_bytes(ptr, 0, capacity);
+        }
+        let mut bstr = Self(ptr);
+
+        let len = f(bstr.0, capacity);
+        assert!(
+            len <= capacity,
+            "the used length `{}` is larger than the capacity `{}`",
+            len,
+            capacity
+        );
+
+        if len != capacity {
+            // `len` fits in a `u32` as it is not larger than `capacity`.
+            let ret = unsafe { SysReAllocStringLen(&mut bstr.0, bstr.0, len as u32) };
+            assert!(ret != 0, "failed to reallocate bstr");
+        }
+
+        Ok(bstr)
+    }
+
+    /// Try to make a new [`BStr`] from an [`OsStr`], ensuring that the result is valid UTF16.
+    ///
+    /// Unlike the `TryFrom<&OsStr>` impl, this rejects strings with unpaired surrogates.
+    ///
+    /// # Errors
+    /// Returns a [`BStrFromOsStrError`] if the string contains an unpaired surrogate or if a new [`BStr`] could not be created.
+    ///
+    pub fn from_os_str_checked(s: &OsStr) -> Result<Self, BStrFromOsStrError> {
+        let mut len = 0;
+        for c in std::char::decode_utf16(s.encode_wide()) {
+            len += c.map_err(BStrFromOsStrError::LoneSurrogate)?.len_utf16();
+        }
+
+        Self::from_wide_iter(s.encode_wide(), len).map_err(BStrFromOsStrError::Creation)
+    }
+
+    /// Try to make a new [`BStr`] from an iterator of fallible chars, like [`BStrRef::chars`].
+    /// This stops at the first error.
+    ///
+    /// The chars are collected into a buffer before the [`BStr`] is allocated.
+    ///
+    /// # Errors
+    /// Returns a [`TryFromCharError`] if the iterator yields an error or if a new [`BStr`] could not be created.
+    ///
+    pub fn try_from_char_results<E>(
+        iter: impl Iterator<Item = Result<char, E>>,
+    ) -> Result<Self, TryFromCharError<E>> {
+        let mut buffer = Vec::with_capacity(iter.size_hint().0);
+        let mut encode_buffer = [0; 2];
+        for c in iter {
+            let c = c.map_err(TryFromCharError::Iter)?;
+            buffer.extend_from_slice(c.encode_utf16(&mut encode_buffer));
+        }
+
+        Self::from_wide_slice(&buffer).map_err(TryFromCharError::Creation)
+    }
+
     /// Make a new [`BStr`] from a raw BSTR ptr.
     ///
+    /// This takes ownership of the `BSTR`, which is freed when the [`BStr`] is dropped.
+    /// Use [`BStrRef::borrow_raw`] for a `BSTR` that is owned by someone else.
+    ///
     /// # Safety
     /// `ptr` must be a `BSTR` allocated with `SysAllocStringLen` or similar.
     ///
@@ -196,6 +396,41 @@ impl BStr {
     pub fn as_mut_bstr_ref(&mut self) -> &mut BStrRef {
         unsafe { BStrRef::from_mut_ptr(self.0) }
     }
+
+    /// Shorten this [`BStr`] to `code_units` wide chars.
+    /// This does nothing if this [`BStr`] is not longer than `code_units`.
+    ///
+    /// The length of a `BSTR` is stored in its allocation,
+    /// so this performs a single reallocation with `SysReAllocStringLen`.
+    ///
+    /// # Errors
+    /// Returns [`BStrCreationError::AllocFailed`] if the `BSTR` could not be reallocated.
+    /// In that case, this [`BStr`] is left unchanged.
+    ///
+    pub fn truncate(&mut self, code_units: usize) -> Result<(), BStrCreationError> {
+        if code_units >= self.as_wide_slice().len() {
+            return Ok(());
+        }
+
+        // `code_units` fits in a `u32` as it is shorter than the current length.
+        let ret = unsafe { SysReAllocStringLen(&mut self.0, self.0, code_units as u32) };
+
+        if ret == 0 {
+            return Err(BStrCreationError::AllocFailed);
+        }
+
+        Ok(())
+    }
+
+    /// Clear this [`BStr`], making it empty.
+    ///
+    /// # Errors
+    /// Returns [`BStrCreationError::AllocFailed`] if the `BSTR` could not be reallocated.
+    /// In that case, this [`BStr`] is left unchanged.
+    ///
+    pub fn clear(&mut self) -> Result<(), BStrCreationError> {
+        self.truncate(0)
+    }
 }
 
 impl Deref for BStr {
@@ -220,6 +455,109 @@ impl Drop for BStr {
     }
 }
 
+/// A pool of scratch buffers for making many [`BStr`]s from [`str`]s.
+///
+/// A `BSTR` is always freed with `SysFreeString`, so the `BSTR`s themselves cannot be pooled.
+/// Instead, this reuses the intermediate wide char buffers used to encode each [`str`],
+/// so that making a [`BStr`] only performs the `BSTR` allocation.
+///
+#[derive(Debug, Default)]
+pub struct BStrPool {
+    buffers: Vec<Vec<u16>>,
+}
+
+impl BStrPool {
+    /// Make a new, empty [`BStrPool`].
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a new [`BStr`] from a [`str`], using a scratch buffer from this pool to encode it.
+    ///
+    /// # Panics
+    /// Panics if a new [`BStr`] could not be allocated or if the length cannot be stored in a [`u32`].
+    ///
+    pub fn acquire(&mut self, s: &str) -> BStr {
+        let mut buffer = self.buffers.pop().unwrap_or_default();
+        buffer.extend(s.encode_utf16());
+
+        let bstr = BStr::from_wide_slice_exact(&buffer).expect("Valid BStr");
+        buffer.clear();
+        self.buffers.push(buffer);
+
+        bstr
+    }
+
+    /// Free a [`BStr`], keeping a scratch buffer large enough to encode it again.
+    ///
+    pub fn release(&mut self, bstr: BStr) {
+        let len = bstr.as_wide_slice().len();
+        drop(bstr);
+
+        match self
+            .buffers
+            .iter_mut()
+            .max_by_key(|buffer| buffer.capacity())
+        {
+            Some(buffer) => buffer.reserve(len),
+            None => self.buffers.push(Vec::with_capacity(len)),
+        }
+    }
+}
+
+/// A writer that collects chunks of wide chars, then makes a single [`BStr`] from them.
+///
+/// This is useful for streams of wide chars, like from a UTF16 encoder, where the final length is not known in advance.
+///
+#[derive(Debug, Default)]
+pub struct BStrWideWriter {
+    buffer: Vec<u16>,
+}
+
+impl BStrWideWriter {
+    /// Make a new, empty [`BStrWideWriter`].
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a new, empty [`BStrWideWriter`] with space for at least `capacity` wide chars.
+    ///
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Append a chunk of wide chars.
+    ///
+    pub fn push_wide(&mut self, data: &[u16]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Get the number of wide chars written so far.
+    ///
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Check if no wide chars have been written.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Make a [`BStr`] from the written wide chars.
+    ///
+    /// # Errors
+    /// Returns a `BStrCreationError` if a new [`BStr`] could not be allocated or if the length cannot be stored in a [`u32`].
+    ///
+    pub fn into_bstr(self) -> Result<BStr, BStrCreationError> {
+        BStr::from_wide_slice_exact(&self.buffer)
+    }
+}
+
 /// A reference to a `BSTR`.
 //
 #[repr(transparent)]
@@ -253,6 +591,22 @@ impl BStrRef {
         &*(ptr as *const Self)
     }
 
+    /// Borrow a `BSTR` that is owned by someone else, like a `BSTR` returned by a COM method that must not be freed by the caller.
+    ///
+    /// Unlike [`BStr::from_raw`], which takes ownership and frees the `BSTR` when dropped, this never frees the `BSTR`.
+    /// The owner remains responsible for freeing it, and must not do so while the returned reference is alive.
+    ///
+    /// # Safety
+    /// The same requirements as [`BStrRef::from_ptr`] apply.
+    /// Notably, the reference must not outlive the pointer, so the chosen lifetime `'a` must end before the owner frees the `BSTR`.
+    ///
+    /// # Panics
+    /// Panics if len cannot fit in a [`usize`]
+    ///
+    pub unsafe fn borrow_raw<'a>(ptr: *const u16) -> &'a Self {
+        Self::from_ptr(ptr)
+    }
+
     /// Make a &mut [`BStrRef`] from a `BSTR` ptr.
     ///
     /// # Safety
@@ -326,6 +680,68 @@ impl BStrRef {
         &self.inner
     }
 
+    /// Copy this [`BStrRef`] into a new wide char [`Vec`].
+    /// This WILL NOT include the terminating NUL byte.
+    /// This MAY or MAY NOT include interior NUL bytes.
+    ///
+    pub fn to_wide_vec(&self) -> Vec<u16> {
+        self.as_wide_slice().to_vec()
+    }
+
+    /// Copy this [`BStrRef`] into a new wide char [`Vec`].
+    /// This WILL include the terminating NUL byte.
+    /// This MAY or MAY NOT include interior NUL bytes.
+    ///
+    pub fn to_wide_vec_with_nul(&self) -> Vec<u16> {
+        self.as_wide_slice_with_nul().to_vec()
+    }
+
+    /// Iterate over the wide chars in this [`BStrRef`].
+    /// This WILL NOT include the terminating NUL byte.
+    ///
+    pub fn iter(&self) -> std::slice::Iter<'_, u16> {
+        self.as_wide_slice().iter()
+    }
+
+    /// Iterate over all overlapping windows of `size` wide chars in this [`BStrRef`].
+    /// This is a convenience function for [`slice::windows`].
+    ///
+    /// # Panics
+    /// Panics if `size` is 0.
+    ///
+    pub fn windows(&self, size: usize) -> std::slice::Windows<'_, u16> {
+        self.as_wide_slice().windows(size)
+    }
+
+    /// Iterate over `chunk_size` wide chars of this [`BStrRef`] at a time.
+    /// The last chunk may be shorter if the length is not divisible by `chunk_size`.
+    /// This is a convenience function for [`slice::chunks`].
+    ///
+    /// # Panics
+    /// Panics if `chunk_size` is 0.
+    ///
+    pub fn chunks(&self, chunk_size: usize) -> std::slice::Chunks<'_, u16> {
+        self.as_wide_slice().chunks(chunk_size)
+    }
+
+    /// Get a sub-slice of the wide chars in this [`BStrRef`], or `None` if the range is out of bounds.
+    /// The range is in wide chars, matching [`BStrRef::as_wide_slice`].
+    ///
+    pub fn get(&self, range: Range<usize>) -> Option<&[u16]> {
+        self.as_wide_slice().get(range)
+    }
+
+    /// Make a new [`BStr`] from a sub-range of the wide chars in this [`BStrRef`], or `None` if the range is out of bounds.
+    /// The range is in wide chars, matching [`BStrRef::as_wide_slice`].
+    ///
+    /// # Panics
+    /// Panics if a new [`BStr`] could not be allocated.
+    ///
+    pub fn substr(&self, range: Range<usize>) -> Option<BStr> {
+        let slice = self.get(range)?;
+        Some(BStr::from_wide_slice_exact(slice).expect("Valid BStr"))
+    }
+
     /// Returns true if this [`BStrRef`] contains any interior NULs.
     ///
     pub fn contains_nul(&self) -> bool {
@@ -346,6 +762,16 @@ impl BStrRef {
         String::from_utf16_lossy(self.as_wide_slice())
     }
 
+    /// Converts this [`BStr`] to a [`String`]. This allocates a new [`String`].
+    /// This is just a convenience function for [`String::from_utf16`].
+    ///
+    /// # Errors
+    /// Returns an error if this contains invalid UTF16.
+    ///
+    pub fn try_to_string(&self) -> Result<String, std::string::FromUtf16Error> {
+        String::from_utf16(self.as_wide_slice())
+    }
+
     /// Make a new [`BStrDisplay`] from this reference.
     /// [`BStrDisplay`] has a lossy display impl.
     ///
@@ -355,8 +781,75 @@ impl BStrRef {
 
     /// Try to iterate over the chars in this string.
     ///
-    pub fn chars(&self) -> impl Iterator<Item = Result<char, std::char::DecodeUtf16Error>> + '_ {
-        std::char::decode_utf16(self.as_wide_slice().iter().copied())
+    pub fn chars(&self) -> std::char::DecodeUtf16<std::iter::Copied<std::slice::Iter<'_, u16>>> {
+        std::char::decode_utf16(self.wide_units())
+    }
+
+    /// Iterate over the raw wide chars in this [`BStrRef`] by value.
+    /// This WILL NOT include the terminating NUL byte.
+    ///
+    pub fn wide_units(&self) -> std::iter::Copied<std::slice::Iter<'_, u16>> {
+        self.iter().copied()
+    }
+
+    /// Get the escaped form of this [`BStrRef`] used by the `Debug` impl as a [`String`], without the surrounding quotes.
+    /// Invalid UTF16 is replaced with [`std::char::REPLACEMENT_CHARACTER`].
+    ///
+    pub fn escape_debug_string(&self) -> String {
+        self.chars()
+            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+            .flat_map(char::escape_debug)
+            .collect()
+    }
+
+    /// Compare this [`BStrRef`] with another by code unit, ignoring the locale, with `CompareStringOrdinal`.
+    ///
+    /// If `ignore_case` is true, the strings are compared using the operating system uppercase table.
+    ///
+    /// # Panics
+    /// Panics if either length cannot fit in an `i32` or if the comparison fails.
+    ///
+    pub fn cmp_ordinal(&self, other: &BStrRef, ignore_case: bool) -> std::cmp::Ordering {
+        let lhs = self.as_wide_slice();
+        let rhs = other.as_wide_slice();
+        let len: c_int = lhs.len().try_into().expect("len does not fit in an `i32`");
+        let other_len: c_int = rhs.len().try_into().expect("len does not fit in an `i32`");
+        let ignore_case = if ignore_case { TRUE } else { FALSE };
+
+        let ret = unsafe {
+            CompareStringOrdinal(lhs.as_ptr(), len, rhs.as_ptr(), other_len, ignore_case)
+        };
+
+        match ret {
+            CSTR_LESS_THAN => std::cmp::Ordering::Less,
+            CSTR_EQUAL => std::cmp::Ordering::Equal,
+            CSTR_GREATER_THAN => std::cmp::Ordering::Greater,
+            _ => panic!(
+                "failed to compare strings ({})",
+                std::io::Error::last_os_error()
+            ),
+        }
+    }
+}
+
+/// This lossily displays the string, replacing invalid UTF16 with `U+FFFD REPLACEMENT CHARACTER`.
+///
+/// This honors the width, precision, and alignment of the formatter, like the `Display` impl of [`str`].
+impl std::fmt::Display for BStrRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Padding and truncation need the whole string, so only allocate when they are requested.
+        if f.width().is_some() || f.precision().is_some() {
+            return f.pad(&self.to_string_lossy());
+        }
+
+        for c in self
+            .chars()
+            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        {
+            f.write_char(c)?
+        }
+
+        Ok(())
     }
 }
 
@@ -390,11 +883,11 @@ impl TryFrom<&str> for BStr {
 impl TryFrom<&OsStr> for BStr {
     type Error = BStrCreationError;
 
-    /// An upfront length calc is needed to allocate the correct size of the underlyting buffer.
-    /// If this is undesirable, allocate to a [`Vec`] and use the `TryFrom<&[u16]>` impl.
+    /// The string is encoded into a [`Vec`] first, as the length of the encoded string is needed to allocate the underlying buffer.
+    /// This avoids encoding the string twice, once to get the length and once to copy the data.
     fn try_from(data: &OsStr) -> Result<Self, Self::Error> {
-        let len = data.encode_wide().count();
-        Self::from_wide_iter(data.encode_wide(), len)
+        let data: Vec<u16> = data.encode_wide().collect();
+        Self::from_wide_slice(&data)
     }
 }
 
@@ -424,7 +917,13 @@ impl FromStr for BStr {
 
 impl std::fmt::Debug for BStr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.as_bstr_ref().fmt(f)
+        std::fmt::Debug::fmt(self.as_bstr_ref(), f)
+    }
+}
+
+impl std::fmt::Display for BStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.as_bstr_ref(), f)
     }
 }
 
@@ -464,6 +963,18 @@ impl PartialEq<&str> for BStr {
     }
 }
 
+impl PartialEq<[u16]> for BStr {
+    fn eq(&self, other: &[u16]) -> bool {
+        self.as_bstr_ref().eq(other)
+    }
+}
+
+impl PartialEq<&[u16]> for BStr {
+    fn eq(&self, other: &&[u16]) -> bool {
+        self.as_bstr_ref().eq(other)
+    }
+}
+
 impl Eq for BStr {}
 
 impl Hash for BStr {
@@ -492,15 +1003,57 @@ impl<'a> std::fmt::Debug for BStrDisplay<'a> {
 
 impl<'a> std::fmt::Display for BStrDisplay<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for c in self
-            .0
-            .chars()
-            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
-        {
-            f.write_char(c)?
+        std::fmt::Display::fmt(self.0, f)
+    }
+}
+
+/// Concatenate two strings by code unit, preserving interior NULs and invalid UTF16.
+///
+/// # Panics
+/// Panics if the combined length cannot fit in a `u32` or if the new [`BStr`] could not be allocated.
+impl Add<&BStrRef> for &BStrRef {
+    type Output = BStr;
+
+    fn add(self, other: &BStrRef) -> Self::Output {
+        let lhs = self.as_wide_slice();
+        let rhs = other.as_wide_slice();
+        let len = checked_wide_len([lhs.len(), rhs.len()].iter().copied())
+            .expect("the combined length is too large");
+        let ptr = unsafe { SysAllocStringLen(std::ptr::null(), len) };
+        assert!(!ptr.is_null(), "failed to allocate bstr");
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(lhs.as_ptr(), ptr, lhs.len());
+            std::ptr::copy_nonoverlapping(rhs.as_ptr(), ptr.add(lhs.len()), rhs.len());
         }
 
-        Ok(())
+        BStr(ptr)
+    }
+}
+
+impl Add<&BStrRef> for &BStr {
+    type Output = BStr;
+
+    fn add(self, other: &BStrRef) -> Self::Output {
+        self.as_bstr_ref() + other
+    }
+}
+
+impl<'a> IntoIterator for &'a BStrRef {
+    type Item = Result<char, std::char::DecodeUtf16Error>;
+    type IntoIter = std::char::DecodeUtf16<std::iter::Copied<std::slice::Iter<'a, u16>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chars()
+    }
+}
+
+impl<'a> IntoIterator for &'a BStr {
+    type Item = Result<char, std::char::DecodeUtf16Error>;
+    type IntoIter = std::char::DecodeUtf16<std::iter::Copied<std::slice::Iter<'a, u16>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chars()
     }
 }
 
@@ -558,6 +1111,18 @@ impl PartialEq<&str> for BStrRef {
     }
 }
 
+impl PartialEq<[u16]> for BStrRef {
+    fn eq(&self, other: &[u16]) -> bool {
+        self.as_wide_slice().eq(other)
+    }
+}
+
+impl PartialEq<&[u16]> for BStrRef {
+    fn eq(&self, other: &&[u16]) -> bool {
+        self.eq(*other)
+    }
+}
+
 impl Eq for BStrRef {}
 
 impl ToOwned for BStrRef {
@@ -671,4 +1236,330 @@ mod test {
 
         assert_eq!(owned_cow_bstr, borrowed_cow_bstr);
     }
+
+    #[test]
+    fn clear_bstr() {
+        let mut s = BStr::new("Hello World!");
+        s.clear().expect("failed to clear");
+        assert!(s.is_empty());
+        assert_eq!(s.as_wide_slice_with_nul(), &[0]);
+    }
+
+    #[test]
+    fn truncate_bstr() {
+        let mut s = BStr::new("hello");
+        s.truncate(3).expect("failed to truncate");
+        assert_eq!(s, "hel");
+        assert_eq!(s.as_wide_slice_with_nul().last(), Some(&0));
+
+        // Truncating to a longer length does nothing.
+        s.truncate(10).expect("failed to truncate");
+        assert_eq!(s, "hel");
+
+        // Lengths between the code unit count and the byte count are also longer.
+        let mut s = BStr::new("hello");
+        s.truncate(7).expect("failed to truncate");
+        assert_eq!(s, "hello");
+        assert_eq!(s.as_wide_slice().len(), 5);
+    }
+
+    #[test]
+    fn escape_debug_string() {
+        let s = BStr::new("a\tb");
+        assert_eq!(s.escape_debug_string(), "a\\tb");
+    }
+
+    #[test]
+    fn from_wide_slice_exact() {
+        let data: Vec<u16> = OsStr::new("Hello World!").encode_wide().collect();
+        let exact = BStr::from_wide_slice_exact(&data).expect("failed to allocate exact");
+        let slice = BStr::from_wide_slice(&data).expect("failed to allocate slice");
+        assert_eq!(exact, slice);
+        assert_eq!(exact.as_wide_slice(), data.as_slice());
+    }
+
+    #[test]
+    fn try_from_char_results() {
+        let s = BStr::new("Hello World! \u{1F600}");
+        let s1 = BStr::try_from_char_results(s.chars()).expect("failed to make bstr");
+        assert_eq!(s, s1);
+
+        let err = BStr::try_from_char_results(vec![Ok('a'), Err(()), Ok('b')].into_iter())
+            .expect_err("did not stop at the error");
+        assert_eq!(err, TryFromCharError::Iter(()));
+    }
+
+    #[test]
+    fn slice_methods() {
+        let s = BStr::new("abcd");
+        let wide: Vec<u16> = OsStr::new("abcd").encode_wide().collect();
+
+        assert!(s.iter().eq(wide.iter()));
+
+        let windows: Vec<&[u16]> = s.windows(2).collect();
+        assert_eq!(windows, vec![&wide[0..2], &wide[1..3], &wide[2..4]]);
+
+        let chunks: Vec<&[u16]> = s.chunks(3).collect();
+        assert_eq!(chunks, vec![&wide[0..3], &wide[3..4]]);
+    }
+
+    #[test]
+    fn checked_wide_len_overflow() {
+        assert_eq!(checked_wide_len([1, 2, 3].iter().copied()), Ok(6));
+        assert_eq!(checked_wide_len(std::iter::empty()), Ok(0));
+
+        assert!(matches!(
+            checked_wide_len([usize::MAX, 1].iter().copied()),
+            Err(BStrCreationError::LenTooLarge(_))
+        ));
+        assert!(matches!(
+            checked_wide_len([u32::MAX as usize, 1].iter().copied()),
+            Err(BStrCreationError::LenTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn from_os_str_checked() {
+        let s = BStr::from_os_str_checked(OsStr::new("Hello World! \u{1F600}"))
+            .expect("failed to make bstr");
+        assert_eq!(s, "Hello World! \u{1F600}");
+
+        let lone_surrogate = OsString::from_wide(&[0x0061, 0xD800, 0x0062]);
+        let err =
+            BStr::from_os_str_checked(&lone_surrogate).expect_err("accepted a lone surrogate");
+        assert!(matches!(err, BStrFromOsStrError::LoneSurrogate(_)));
+
+        // The unchecked conversion still accepts it.
+        let s = BStr::try_from(lone_surrogate.as_os_str()).expect("failed to make bstr");
+        assert_eq!(s.as_wide_slice(), &[0x0061, 0xD800, 0x0062]);
+    }
+
+    #[test]
+    fn display_matches_bstr_display() {
+        let s = BStr::from_wide_slice(&[0x0061, 0xD800, 0x0062]).expect("failed to make bstr");
+        assert_eq!(format!("{}", s), format!("{}", s.display()));
+        assert_eq!(format!("{}", &*s), format!("{}", s.display()));
+        assert_eq!(format!("{}", s), "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn into_iter_chars() {
+        let s = BStr::from_wide_slice(&[0x0061, 0xD800, 0x0062]).expect("failed to make bstr");
+
+        let mut string = String::new();
+        for c in &s {
+            string.push(c.unwrap_or(std::char::REPLACEMENT_CHARACTER));
+        }
+        assert_eq!(string, "a\u{FFFD}b");
+
+        let units: Vec<u16> = s.wide_units().collect();
+        assert_eq!(units, [0x0061, 0xD800, 0x0062]);
+    }
+
+    #[test]
+    fn build_with() {
+        /// Fill a buffer like `GetUserNameW`, returning the length written.
+        fn fill(buffer: *mut u16, buffer_len: usize) -> usize {
+            let data: Vec<u16> = "user".encode_utf16().collect();
+            assert!(data.len() <= buffer_len);
+            unsafe {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len());
+            }
+            data.len()
+        }
+
+        let s = BStr::build_with(256, fill).expect("failed to make bstr");
+        assert_eq!(s, "user");
+        assert_eq!(s.len(), 4);
+
+        let s = BStr::build_with(4, fill).expect("failed to make bstr");
+        assert_eq!(s, "user");
+
+        let s = BStr::build_with(8, |_, _| 0).expect("failed to make bstr");
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn cmp_ordinal() {
+        let upper = BStr::try_from("Apple").expect("failed to make bstr");
+        let lower = BStr::try_from("apple").expect("failed to make bstr");
+
+        assert_eq!(upper.cmp_ordinal(&lower, true), std::cmp::Ordering::Equal);
+        assert_eq!(upper.cmp_ordinal(&lower, false), std::cmp::Ordering::Less);
+        assert_eq!(
+            lower.cmp_ordinal(&upper, false),
+            std::cmp::Ordering::Greater
+        );
+
+        let short = BStr::try_from("app").expect("failed to make bstr");
+        assert_eq!(short.cmp_ordinal(&lower, false), std::cmp::Ordering::Less);
+        assert_eq!(
+            lower.cmp_ordinal(&short, false),
+            std::cmp::Ordering::Greater
+        );
+        assert_eq!(short.cmp_ordinal(&upper, true), std::cmp::Ordering::Less);
+    }
+
+    #[test]
+    fn add() {
+        let foo = BStr::try_from("foo\0").expect("failed to make bstr");
+        let bar = BStr::try_from("bar").expect("failed to make bstr");
+
+        let combined = &foo + &bar;
+        assert_eq!(combined.as_wide_slice().len(), 7);
+        assert_eq!(combined, "foo\0bar");
+        assert!(combined.contains_nul());
+
+        let combined = &*foo + &bar;
+        assert_eq!(combined, "foo\0bar");
+    }
+
+    #[test]
+    fn try_to_string() {
+        let s = BStr::new("Hello World!");
+        assert_eq!(s.try_to_string().expect("valid utf16"), "Hello World!");
+        assert_eq!(s.to_string(), "Hello World!");
+
+        let s = BStr::from_wide_slice(&[0x0061, 0xD800, 0x0062]).expect("failed to make bstr");
+        assert!(s.try_to_string().is_err());
+        assert!(s.as_bstr_ref().try_to_string().is_err());
+        assert_eq!(s.to_string_lossy(), "a\u{FFFD}b");
+    }
+
+    #[test]
+    fn bstr_pool() {
+        let mut pool = BStrPool::new();
+
+        for i in 0..100 {
+            let data = format!("Hello World! {}", i);
+            let bstr = pool.acquire(&data);
+            assert_eq!(bstr, data.as_str());
+            pool.release(bstr);
+        }
+
+        // Only one scratch buffer is needed as each bstr was released before the next was acquired.
+        assert_eq!(pool.buffers.len(), 1);
+
+        let a = pool.acquire("a");
+        let b = pool.acquire("b");
+        assert_eq!(a, "a");
+        assert_eq!(b, "b");
+        pool.release(a);
+        pool.release(b);
+    }
+
+    #[test]
+    fn bstr_wide_writer() {
+        let mut writer = BStrWideWriter::new();
+        let hello: Vec<u16> = "Hello ".encode_utf16().collect();
+        let world: Vec<u16> = "World!".encode_utf16().collect();
+        writer.push_wide(&hello);
+        writer.push_wide(&world);
+        assert_eq!(writer.len(), 12);
+
+        let s = writer.into_bstr().expect("failed to make bstr");
+        assert_eq!(s, "Hello World!");
+
+        let s = BStrWideWriter::new()
+            .into_bstr()
+            .expect("failed to make bstr");
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn get_and_substr() {
+        let s = BStr::new("Hello World!");
+
+        let expected: Vec<u16> = "World".encode_utf16().collect();
+        assert_eq!(s.get(6..11), Some(expected.as_slice()));
+        assert_eq!(s.substr(6..11).expect("in bounds"), "World");
+        assert_eq!(s.substr(0..0).expect("in bounds"), "");
+
+        assert_eq!(s.get(6..13), None);
+        assert!(s.substr(13..14).is_none());
+    }
+
+    #[test]
+    fn display_width_and_precision() {
+        let s = BStr::new("Hello");
+
+        assert_eq!(format!("{:>10}", s), "     Hello");
+        assert_eq!(format!("{:<10}|", s), "Hello     |");
+        assert_eq!(format!("{:^9}", s), "  Hello  ");
+        assert_eq!(format!("{:.3}", s), "Hel");
+        assert_eq!(format!("{:>5.3}", &*s), "  Hel");
+        assert_eq!(format!("{:>5.3}", s.display()), "  Hel");
+        assert_eq!(format!("{:2}", s), "Hello");
+    }
+
+    #[test]
+    fn borrow_raw() {
+        let owner = BStr::new("Hello World!");
+
+        {
+            let borrowed = unsafe { BStrRef::borrow_raw(owner.as_ptr()) };
+            assert_eq!(borrowed, "Hello World!");
+            assert_eq!(borrowed.as_ptr(), owner.as_ptr());
+        }
+
+        // The owner is still valid and frees the string exactly once when dropped.
+        assert_eq!(owner, "Hello World!");
+    }
+
+    #[test]
+    fn from_wide_iter_exact() {
+        let data: Vec<u16> = "Hello World!".encode_utf16().collect();
+        let s = BStr::from_wide_iter_exact(data.iter().copied()).expect("failed to make bstr");
+        assert_eq!(s, "Hello World!");
+
+        let s = BStr::from_wide_iter_exact(std::iter::empty()).expect("failed to make bstr");
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn try_from_large_os_str() {
+        let data: String = "The quick brown fox jumps over the lazy dog. \u{1F98A}"
+            .chars()
+            .cycle()
+            .take(1024 * 1024)
+            .collect();
+
+        let s = BStr::new(OsStr::new(&data));
+        assert_eq!(s.as_wide_slice().len(), data.encode_utf16().count());
+        assert_eq!(s, data.as_str());
+        assert_eq!(s.try_to_string().expect("invalid utf16"), data);
+    }
+
+    #[test]
+    fn eq_wide_slice() {
+        let s = BStr::new("Hello World!");
+        let wide: Vec<u16> = OsStr::new("Hello World!").encode_wide().collect();
+
+        assert_eq!(s, wide.as_slice());
+        assert_eq!(s, *wide.as_slice());
+        assert_eq!(*s, wide.as_slice());
+        assert_eq!(*s, *wide.as_slice());
+
+        // The NUL terminator is not part of the content.
+        let wide_with_nul: Vec<u16> = wide.iter().copied().chain(std::iter::once(0)).collect();
+        assert_ne!(s, wide_with_nul.as_slice());
+        assert_ne!(s, &wide[..5]);
+    }
+
+    #[test]
+    fn to_wide_vec() {
+        let s = BStr::new("Hello World!");
+        let expected: Vec<u16> = "Hello World!".encode_utf16().collect();
+
+        let wide = s.to_wide_vec();
+        let wide_with_nul = s.to_wide_vec_with_nul();
+        assert_eq!(wide, expected);
+        assert_eq!(wide_with_nul.len(), wide.len() + 1);
+        assert_eq!(wide_with_nul[..wide.len()], wide[..]);
+        assert_eq!(wide_with_nul.last(), Some(&0));
+
+        let empty = BStr::new("");
+        assert!(empty.to_wide_vec().is_empty());
+        assert_eq!(empty.to_wide_vec_with_nul(), [0]);
+    }
 }
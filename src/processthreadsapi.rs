@@ -1,12 +1,75 @@
 use crate::handleapi::Handle;
+use crate::handleapi::OwnedHandle;
+use crate::handleapi::WaitResult;
+use crate::libloaderapi::HModule;
+use std::convert::TryInto;
+use std::ffi::CStr;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+use winapi::shared::minwindef::BOOL;
 use winapi::shared::minwindef::DWORD;
 use winapi::shared::minwindef::FALSE;
+use winapi::shared::minwindef::LPARAM;
+use winapi::shared::minwindef::TRUE;
+use winapi::shared::ntdef::NTSTATUS;
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::ERROR_NOT_FOUND;
+use winapi::um::debugapi::DebugActiveProcess;
+use winapi::um::debugapi::DebugActiveProcessStop;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::ioapiset::CancelSynchronousIo;
+use winapi::um::processthreadsapi::GetExitCodeThread;
+use winapi::um::processthreadsapi::GetProcessHandleCount;
+use winapi::um::processthreadsapi::GetProcessId;
+use winapi::um::processthreadsapi::GetProcessPriorityBoost;
+use winapi::um::processthreadsapi::GetProcessShutdownParameters;
 use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::processthreadsapi::OpenThread;
+use winapi::um::processthreadsapi::QueueUserAPC;
+use winapi::um::processthreadsapi::ResumeThread;
+use winapi::um::processthreadsapi::SetProcessPriorityBoost;
+use winapi::um::processthreadsapi::SetProcessShutdownParameters;
+use winapi::um::processthreadsapi::SuspendThread;
 use winapi::um::processthreadsapi::TerminateProcess;
+use winapi::um::psapi::K32EnumProcesses;
+use winapi::um::psapi::K32GetModuleBaseNameW;
+use winapi::um::psapi::K32GetProcessMemoryInfo;
+use winapi::um::psapi::PROCESS_MEMORY_COUNTERS;
 use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::tlhelp32::CreateToolhelp32Snapshot;
+use winapi::um::tlhelp32::Thread32First;
+use winapi::um::tlhelp32::Thread32Next;
+use winapi::um::tlhelp32::TH32CS_SNAPTHREAD;
+use winapi::um::tlhelp32::THREADENTRY32;
+use winapi::um::winbase::GetProcessDEPPolicy;
 use winapi::um::winbase::WAIT_FAILED;
+use winapi::um::winnt::HANDLE;
+use winapi::um::winnt::PROCESS_ALL_ACCESS;
+use winapi::um::winnt::PROCESS_QUERY_INFORMATION;
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winnt::PROCESS_SET_INFORMATION;
+use winapi::um::winnt::PROCESS_SUSPEND_RESUME;
 use winapi::um::winnt::PROCESS_TERMINATE;
+use winapi::um::winnt::PROCESS_VM_READ;
 use winapi::um::winnt::SYNCHRONIZE;
+use winapi::um::winnt::THREAD_QUERY_INFORMATION;
+use winapi::um::winnt::THREAD_SET_CONTEXT;
+use winapi::um::winnt::THREAD_SUSPEND_RESUME;
+use winapi::um::winnt::THREAD_TERMINATE;
+use winapi::um::winuser::EnumWindows;
+use winapi::um::winuser::GetWindowThreadProcessId;
+use winapi::um::winuser::PostMessageW;
+use winapi::um::winuser::WM_CLOSE;
+
+/// DEP is enabled for a process.
+///
+/// This is not exposed by `winapi`.
+const PROCESS_DEP_ENABLE: DWORD = 0x0000_0001;
+
+/// The signature of `NtSuspendProcess` and `NtResumeProcess`.
+type NtProcessFn = unsafe extern "system" fn(HANDLE) -> NTSTATUS;
 
 // TODO: Finish Flags
 bitflags::bitflags! {
@@ -18,13 +81,92 @@ bitflags::bitflags! {
         ///
         const TERMINATE = PROCESS_TERMINATE;
 
+        /// Query information right
+        ///
+        const QUERY_INFORMATION = PROCESS_QUERY_INFORMATION;
+
+        /// Query limited information right
+        ///
+        const QUERY_LIMITED_INFORMATION = PROCESS_QUERY_LIMITED_INFORMATION;
+
+        /// Set information right
+        ///
+        const SET_INFORMATION = PROCESS_SET_INFORMATION;
+
+        /// Suspend and resume right
+        ///
+        const SUSPEND_RESUME = PROCESS_SUSPEND_RESUME;
+
+        /// All possible access rights
+        ///
+        const ALL_ACCESS = PROCESS_ALL_ACCESS;
+
+        /// Read virtual memory right
+        ///
+        const VM_READ = PROCESS_VM_READ;
+
         /// Synchronize right
         ///
         const SYNCHRONIZE = SYNCHRONIZE;
     }
 }
 
+// TODO: Finish Flags
+bitflags::bitflags! {
+    /// Thread access rights for opening access to a thread.
+    ///
+    pub struct ThreadAccessRights: DWORD {
+        /// Query information right
+        ///
+        const QUERY_INFORMATION = THREAD_QUERY_INFORMATION;
+
+        /// Set context right
+        ///
+        const SET_CONTEXT = THREAD_SET_CONTEXT;
+
+        /// Suspend and resume right
+        ///
+        const SUSPEND_RESUME = THREAD_SUSPEND_RESUME;
+
+        /// Terminate right
+        ///
+        const TERMINATE = THREAD_TERMINATE;
+
+        /// Synchronize right
+        ///
+        const SYNCHRONIZE = SYNCHRONIZE;
+    }
+}
+
+/// The DEP policy of a process.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct DepPolicy {
+    /// Whether DEP is enabled
+    pub enabled: bool,
+
+    /// Whether the DEP policy is permanent and cannot be changed
+    pub permanent: bool,
+}
+
+/// Memory usage info of a process, as returned by [`Process::memory_info`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ProcessMemoryInfo {
+    /// The number of page faults
+    pub page_fault_count: u32,
+
+    /// The peak working set size, in bytes
+    pub peak_working_set_size: usize,
+
+    /// The current working set size, in bytes
+    pub working_set_size: usize,
+
+    /// The amount of memory committed for this process, in bytes
+    pub pagefile_usage: usize,
+}
+
 /// A Process
+///
+/// This is `Send` and `Sync`, since the underlying [`Handle`] is.
 #[derive(Debug)]
 pub struct Process(Handle);
 
@@ -44,6 +186,306 @@ impl Process {
         }
     }
 
+    /// Open an existing process with all possible access rights.
+    ///
+    /// Opening a process owned by another user or running at a higher integrity level
+    /// usually requires the calling process to be elevated and to have the `SeDebugPrivilege` privilege enabled.
+    /// Prefer [`Process::open`] with only the needed access rights where possible.
+    ///
+    /// # Errors
+    /// Fails if the process could not be opened.
+    ///
+    pub fn open_all(pid: u32) -> std::io::Result<Self> {
+        Self::open(ProcessAccessRights::ALL_ACCESS, pid)
+    }
+
+    /// Get the PID of this process.
+    /// This requires the `QUERY_INFORMATION` or `QUERY_LIMITED_INFORMATION` permission.
+    ///
+    /// # Errors
+    /// Fails if the PID could not be retrieved.
+    ///
+    pub fn pid(&self) -> std::io::Result<u32> {
+        let pid = unsafe { GetProcessId(self.0.as_raw().cast()) };
+
+        if pid == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(pid)
+    }
+
+    /// Get the number of open handles held by this process.
+    /// This requires the `QUERY_INFORMATION` or `QUERY_LIMITED_INFORMATION` permission.
+    ///
+    /// # Errors
+    /// Fails if the handle count could not be retrieved.
+    ///
+    pub fn handle_count(&self) -> std::io::Result<u32> {
+        let mut count = 0;
+        if unsafe { GetProcessHandleCount(self.0.as_raw().cast(), &mut count) == FALSE } {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(count)
+    }
+
+    /// Get the base name of the exe of this process, like `explorer.exe`.
+    /// This requires the `QUERY_INFORMATION` and `VM_READ` permissions.
+    ///
+    /// # Errors
+    /// Fails if the base name could not be retrieved.
+    ///
+    pub fn base_name(&self) -> std::io::Result<OsString> {
+        let mut buffer: Vec<u16> = Vec::with_capacity(260);
+
+        loop {
+            let buffer_len: u32 = buffer
+                .capacity()
+                .try_into()
+                .expect("buffer capacity cannot fit in a `u32`");
+
+            // # Safety
+            // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+            let ret = unsafe {
+                K32GetModuleBaseNameW(
+                    self.0.as_raw().cast(),
+                    std::ptr::null_mut(),
+                    buffer.as_mut_ptr(),
+                    buffer_len,
+                )
+            };
+
+            if ret == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            // The name is silently truncated if the buffer is too small, so a full buffer may be truncated.
+            if ret < buffer_len {
+                // # Safety
+                // The length of data (not including the nul terminator) has been returned and is within the buffer's capacity.
+                unsafe {
+                    buffer.set_len(ret as usize);
+                }
+
+                return Ok(OsString::from_wide(&buffer));
+            }
+
+            buffer.reserve(buffer.capacity() * 2);
+        }
+    }
+
+    /// Get the memory usage of this process.
+    /// This requires the `QUERY_INFORMATION` or `QUERY_LIMITED_INFORMATION` permission.
+    ///
+    /// # Errors
+    /// Fails if the memory usage could not be retrieved.
+    ///
+    pub fn memory_info(&self) -> std::io::Result<ProcessMemoryInfo> {
+        let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as DWORD;
+        counters.cb = size;
+
+        if unsafe { K32GetProcessMemoryInfo(self.0.as_raw().cast(), &mut counters, size) == FALSE }
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(ProcessMemoryInfo {
+            page_fault_count: counters.PageFaultCount,
+            peak_working_set_size: counters.PeakWorkingSetSize,
+            working_set_size: counters.WorkingSetSize,
+            pagefile_usage: counters.PagefileUsage,
+        })
+    }
+
+    /// Attach the calling process to this process as a debugger.
+    /// This requires the `QUERY_INFORMATION` or `QUERY_LIMITED_INFORMATION` permission,
+    /// as well as the privileges needed to debug the target process.
+    ///
+    /// This only attaches. Debug events must be pumped separately with `WaitForDebugEvent`,
+    /// and the debuggee is killed when the calling thread exits unless it is detached first.
+    ///
+    /// # Errors
+    /// Fails if the debugger could not be attached.
+    /// This is usually a permissions error.
+    ///
+    pub fn debug_attach(&self) -> std::io::Result<()> {
+        let pid = self.pid()?;
+
+        if unsafe { DebugActiveProcess(pid) == FALSE } {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Detach the calling process from this process as a debugger.
+    /// This requires the `QUERY_INFORMATION` or `QUERY_LIMITED_INFORMATION` permission.
+    ///
+    /// # Errors
+    /// Fails if the debugger could not be detached.
+    ///
+    pub fn debug_detach(&self) -> std::io::Result<()> {
+        let pid = self.pid()?;
+
+        if unsafe { DebugActiveProcessStop(pid) == FALSE } {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Get the DEP policy of this process.
+    /// This requires the `QUERY_INFORMATION` permission.
+    ///
+    /// This is only meaningful for 32-bit processes, as DEP is always enabled for 64-bit processes.
+    ///
+    /// # Errors
+    /// Fails if the DEP policy could not be retrieved.
+    /// This will fail for 64-bit processes.
+    ///
+    pub fn dep_policy(&self) -> std::io::Result<DepPolicy> {
+        let mut flags = 0;
+        let mut permanent = FALSE;
+        let ret =
+            unsafe { GetProcessDEPPolicy(self.0.as_raw().cast(), &mut flags, &mut permanent) };
+
+        if ret == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(DepPolicy {
+            enabled: flags & PROCESS_DEP_ENABLE != 0,
+            permanent: permanent != FALSE,
+        })
+    }
+
+    /// Check if dynamic priority boosting is disabled for the threads of this process.
+    /// This requires the `QUERY_INFORMATION` or `QUERY_LIMITED_INFORMATION` permission.
+    ///
+    /// Like `GetProcessPriorityBoost`, this returns `true` if boosting is DISABLED.
+    ///
+    /// # Errors
+    /// Fails if the priority boost state could not be retrieved.
+    ///
+    pub fn priority_boost(&self) -> std::io::Result<bool> {
+        let mut disabled = FALSE;
+        if unsafe { GetProcessPriorityBoost(self.0.as_raw().cast(), &mut disabled) == FALSE } {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(disabled != FALSE)
+    }
+
+    /// Disable or enable dynamic priority boosting for the threads of this process.
+    /// This requires the `SET_INFORMATION` permission.
+    ///
+    /// # Errors
+    /// Fails if the priority boost state could not be set.
+    ///
+    pub fn set_priority_boost(&self, disable: bool) -> std::io::Result<()> {
+        let disable = if disable { TRUE } else { FALSE };
+        if unsafe { SetProcessPriorityBoost(self.0.as_raw().cast(), disable) == FALSE } {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Suspend all threads of this process.
+    /// This requires the `SUSPEND_RESUME` and `QUERY_LIMITED_INFORMATION` permissions.
+    ///
+    /// This uses `NtSuspendProcess` from `ntdll`, which suspends the process atomically.
+    /// If it cannot be resolved, this falls back to suspending each thread of the process one by one,
+    /// which may miss threads that are created during the sweep.
+    ///
+    /// Suspensions are counted, so each call must be matched by a call to [`Process::thaw`].
+    ///
+    /// # Errors
+    /// Fails if the process could not be suspended.
+    ///
+    pub fn freeze(&self) -> std::io::Result<()> {
+        let name = CStr::from_bytes_with_nul(b"NtSuspendProcess\0").expect("invalid name");
+        match self.call_nt_process_fn(name) {
+            Some(result) => result,
+            None => self.sweep_threads(|handle| unsafe { SuspendThread(handle) }),
+        }
+    }
+
+    /// Resume all threads of this process, undoing a call to [`Process::freeze`].
+    /// This requires the `SUSPEND_RESUME` and `QUERY_LIMITED_INFORMATION` permissions.
+    ///
+    /// This uses `NtResumeProcess` from `ntdll`, falling back to resuming each thread of the process one by one.
+    ///
+    /// # Errors
+    /// Fails if the process could not be resumed.
+    ///
+    pub fn thaw(&self) -> std::io::Result<()> {
+        let name = CStr::from_bytes_with_nul(b"NtResumeProcess\0").expect("invalid name");
+        match self.call_nt_process_fn(name) {
+            Some(result) => result,
+            None => self.sweep_threads(|handle| unsafe { ResumeThread(handle) }),
+        }
+    }
+
+    /// Resolve a [`NtProcessFn`] from `ntdll` by name and call it on this process.
+    ///
+    /// Returns `None` if the function could not be resolved.
+    fn call_nt_process_fn(&self, name: &CStr) -> Option<std::io::Result<()>> {
+        let ntdll = unsafe { HModule::load(OsStr::new("ntdll.dll")) }.ok()?;
+        let func = ntdll.get_proc_address(name).ok()?;
+
+        // # Safety
+        // Both `NtSuspendProcess` and `NtResumeProcess` match the signature of `NtProcessFn`.
+        let func: NtProcessFn = unsafe { std::mem::transmute(func.as_ptr()) };
+        let status = unsafe { func(self.0.as_raw().cast()) };
+
+        if status < 0 {
+            return Some(Err(std::io::Error::other(format!(
+                "{} failed with status {:#010X}",
+                name.to_string_lossy(),
+                status
+            ))));
+        }
+
+        Some(Ok(()))
+    }
+
+    /// Call `func` on a handle to each thread of this process, like `SuspendThread` or `ResumeThread`.
+    ///
+    /// `func` must return `u32::MAX` on failure.
+    fn sweep_threads(&self, func: impl Fn(HANDLE) -> DWORD) -> std::io::Result<()> {
+        let pid = self.pid()?;
+
+        let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+        let snapshot = unsafe { Handle::from_raw(snapshot.cast()) };
+
+        let mut entry: THREADENTRY32 = unsafe { std::mem::zeroed() };
+        entry.dwSize = std::mem::size_of::<THREADENTRY32>() as DWORD;
+
+        let mut has_more = unsafe { Thread32First(snapshot.as_raw().cast(), &mut entry) == TRUE };
+        while has_more {
+            // The thread may have exited since the snapshot was taken, so failing to open it is ignored.
+            if entry.th32OwnerProcessID == pid {
+                if let Ok(thread) =
+                    Thread::open(ThreadAccessRights::SUSPEND_RESUME, entry.th32ThreadID)
+                {
+                    if func(thread.0.as_raw().cast()) == u32::MAX {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+            }
+
+            has_more = unsafe { Thread32Next(snapshot.as_raw().cast(), &mut entry) == TRUE };
+        }
+
+        Ok(())
+    }
+
     /// Signal this process to terminate.
     /// This requires the `TERMINATE` permission.
     ///
@@ -59,6 +501,77 @@ impl Process {
         Ok(())
     }
 
+    /// Terminate this process and wait for it to exit, until the given interval elapses,
+    /// immediately if it is 0, and indefinitely if it is `u32::MAX`.
+    /// This requires the `TERMINATE` and `SYNCHRONIZE` permissions.
+    ///
+    /// `TerminateProcess` is asynchronous, so the process may still be running right after [`Process::terminate`] returns.
+    /// If this returns [`WaitResult::Signaled`], the process has exited and its exit code is available.
+    ///
+    /// # Errors
+    /// Fails if this process could not be terminated or waited on.
+    ///
+    pub fn kill_and_wait(&self, exit_code: u32, millis: u32) -> std::io::Result<WaitResult> {
+        self.terminate(exit_code)?;
+        self.0.wait(millis)
+    }
+
+    /// Ask this process to close by posting `WM_CLOSE` to its top-level windows,
+    /// and terminate it if it is still running after `timeout_ms` milliseconds.
+    /// This requires the `QUERY_LIMITED_INFORMATION`, `SYNCHRONIZE`, and `TERMINATE` permissions.
+    ///
+    /// If the process has no top-level windows, like most console processes, it is terminated immediately.
+    ///
+    /// Returns `true` if the process had to be terminated.
+    ///
+    /// # Errors
+    /// Fails if this process could not be waited on or terminated.
+    ///
+    pub fn terminate_graceful(&self, timeout_ms: u32, exit_code: u32) -> std::io::Result<bool> {
+        if self.post_close_to_windows()? && self.0.wait(timeout_ms)? == WaitResult::Signaled {
+            return Ok(false);
+        }
+
+        self.terminate(exit_code)?;
+
+        Ok(true)
+    }
+
+    /// Post `WM_CLOSE` to the top-level windows of this process.
+    ///
+    /// Returns `true` if any windows were found.
+    fn post_close_to_windows(&self) -> std::io::Result<bool> {
+        struct State {
+            pid: u32,
+            found: bool,
+        }
+
+        unsafe extern "system" fn callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+            let state = &mut *(lparam as *mut State);
+
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            if pid == state.pid {
+                state.found = true;
+                // The window may have been destroyed since it was enumerated.
+                PostMessageW(hwnd, WM_CLOSE, 0, 0);
+            }
+
+            TRUE
+        }
+
+        let mut state = State {
+            pid: self.pid()?,
+            found: false,
+        };
+
+        if unsafe { EnumWindows(Some(callback), &mut state as *mut State as LPARAM) } == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(state.found)
+    }
+
     /// Wait for this process to terminate until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
     /// This requires the `SYNCHRONIZE` permission.
     ///
@@ -81,6 +594,569 @@ impl Process {
     /// Returns an error which contains this object if this object could not be destroyed.
     ///
     pub fn close(self) -> Result<(), (Self, std::io::Error)> {
-        self.0.close().map_err(|(handle, err)| (Self(handle), err))
+        OwnedHandle::close(self)
+    }
+}
+
+impl OwnedHandle for Process {
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn into_handle(self) -> Handle {
+        self.0
+    }
+}
+
+/// A Thread
+///
+/// This is `Send` and `Sync`, since the underlying [`Handle`] is.
+#[derive(Debug)]
+pub struct Thread(Handle);
+
+impl Thread {
+    /// Open an existing thread.
+    ///
+    /// # Errors
+    /// Fails if the thread could not be opened.
+    ///
+    pub fn open(access_rights: ThreadAccessRights, tid: u32) -> std::io::Result<Self> {
+        let handle = unsafe { OpenThread(access_rights.bits(), FALSE, tid as DWORD) };
+
+        if handle.is_null() {
+            Err(std::io::Error::last_os_error())
+        } else {
+            unsafe { Ok(Self(Handle::from_raw(handle.cast()))) }
+        }
+    }
+
+    /// Get the exit code of this thread.
+    /// This requires the `QUERY_INFORMATION` permission.
+    ///
+    /// If the thread has not terminated, this returns `STILL_ACTIVE` (259).
+    /// Note that a thread may also exit with that value, so use [`Thread::wait`] with a timeout of 0 to tell the two apart.
+    ///
+    /// # Errors
+    /// Fails if the exit code could not be retrieved.
+    ///
+    pub fn exit_code(&self) -> std::io::Result<u32> {
+        let mut exit_code = 0;
+        if unsafe { GetExitCodeThread(self.0.as_raw().cast(), &mut exit_code) == FALSE } {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(exit_code)
+    }
+
+    /// Queue a user-mode asynchronous procedure call to this thread.
+    /// This requires the `SET_CONTEXT` permission.
+    ///
+    /// `func` is called with `data` the next time this thread enters an alertable wait.
+    /// If the thread never enters an alertable wait, `func` is never called.
+    ///
+    /// # Safety
+    /// `func` must be the address of an `unsafe extern "system" fn(usize)` that is safe to call with `data` on this thread.
+    /// If this thread is in another process, `func` must be valid in that process.
+    ///
+    /// # Errors
+    /// Fails if the APC could not be queued.
+    ///
+    pub unsafe fn queue_user_apc(&self, func: usize, data: usize) -> std::io::Result<()> {
+        let func: unsafe extern "system" fn(usize) = std::mem::transmute(func);
+        if QueueUserAPC(Some(func), self.0.as_raw().cast(), data) == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Wait for this thread to terminate until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
+    /// This requires the `SYNCHRONIZE` permission.
+    ///
+    /// Returns [`WaitResult::Signaled`] if the thread has terminated, and [`WaitResult::Timeout`] if it is still running.
+    ///
+    /// # Errors
+    /// Fails if this thread was not waited on.
+    ///
+    pub fn wait(&self, millis: u32) -> std::io::Result<WaitResult> {
+        self.0.wait(millis)
+    }
+
+    /// Cancel the synchronous I/O operation this thread is blocked on.
+    /// This requires the `TERMINATE` permission.
+    ///
+    /// Returns `false` if this thread has no synchronous I/O to cancel.
+    ///
+    /// # Errors
+    /// Fails if the I/O operation could not be cancelled.
+    ///
+    pub fn cancel_synchronous_io(&self) -> std::io::Result<bool> {
+        if unsafe { CancelSynchronousIo(self.0.as_raw().cast()) } == FALSE {
+            if unsafe { GetLastError() } == ERROR_NOT_FOUND {
+                return Ok(false);
+            }
+
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(true)
+    }
+
+    /// Try to close this [`Thread`] handle.
+    ///
+    /// # Errors
+    /// Returns an error which contains this object if this object could not be destroyed.
+    ///
+    pub fn close(self) -> Result<(), (Self, std::io::Error)> {
+        OwnedHandle::close(self)
+    }
+}
+
+impl OwnedHandle for Thread {
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn into_handle(self) -> Handle {
+        self.0
+    }
+}
+
+/// Get the PIDs of all processes on the system.
+///
+/// This is faster than iterating over the processes of a `Snapshot`, but does not provide any other process info.
+///
+/// # Errors
+/// Fails if the processes could not be enumerated.
+///
+pub fn enum_process_ids() -> std::io::Result<Vec<u32>> {
+    let mut pids: Vec<u32> = Vec::with_capacity(1024);
+
+    loop {
+        let buffer_size: u32 = (pids.capacity() * std::mem::size_of::<u32>())
+            .try_into()
+            .expect("buffer size cannot fit in a `u32`");
+        let mut bytes_returned = 0;
+
+        let ret = unsafe { K32EnumProcesses(pids.as_mut_ptr(), buffer_size, &mut bytes_returned) };
+
+        if ret == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // The list is silently truncated if the buffer is too small, so a full buffer may be truncated.
+        if bytes_returned < buffer_size {
+            // # Safety
+            // The number of bytes written has been returned and is within the buffer's capacity.
+            unsafe {
+                pids.set_len(bytes_returned as usize / std::mem::size_of::<u32>());
+            }
+
+            return Ok(pids);
+        }
+
+        pids.reserve(pids.capacity() * 2);
+    }
+}
+
+/// Get the shutdown parameters of the calling process, as a tuple of the shutdown level and flags.
+///
+/// Processes with higher shutdown levels are shut down first, from `0x100` to `0x4FF` for applications.
+///
+/// # Errors
+/// Fails if the shutdown parameters could not be retrieved.
+///
+pub fn get_process_shutdown_parameters() -> std::io::Result<(u32, u32)> {
+    let mut level = 0;
+    let mut flags = 0;
+    if unsafe { GetProcessShutdownParameters(&mut level, &mut flags) == FALSE } {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok((level, flags))
+}
+
+/// Set the shutdown parameters of the calling process.
+///
+/// Processes with higher shutdown levels are shut down first, from `0x100` to `0x4FF` for applications.
+/// `flags` may be `SHUTDOWN_NORETRY` (`0x1`) to skip the retry dialog if the process takes too long to shut down.
+///
+/// # Errors
+/// Fails if the shutdown parameters could not be set, like if the level is out of range.
+///
+pub fn set_process_shutdown_parameters(level: u32, flags: u32) -> std::io::Result<()> {
+    if unsafe { SetProcessShutdownParameters(level, flags) == FALSE } {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use winapi::shared::minwindef::LPVOID;
+    use winapi::shared::winerror::ERROR_INVALID_HANDLE;
+    use winapi::shared::winerror::ERROR_INVALID_PARAMETER;
+    use winapi::shared::winerror::ERROR_NOT_SUPPORTED;
+    use winapi::um::processthreadsapi::CreateThread;
+    use winapi::um::processthreadsapi::GetCurrentProcessId;
+    use winapi::um::processthreadsapi::GetCurrentThreadId;
+    use winapi::um::processthreadsapi::GetProcessTimes;
+    use winapi::um::synchapi::CreateEventW;
+    use winapi::um::synchapi::SleepEx;
+    use winapi::um::winbase::INFINITE;
+
+    /// A handle value that is not open, used to simulate a failing close.
+    const INVALID_HANDLE: usize = 0x7FFF_FFF0;
+
+    #[test]
+    fn process_close_failure_returns_self() {
+        let process = unsafe { Process(Handle::from_raw(INVALID_HANDLE as _)) };
+        let (process, err) = process.close().expect_err("closed an invalid handle");
+        assert_eq!(process.0.as_raw() as usize, INVALID_HANDLE);
+        assert_eq!(err.raw_os_error(), Some(ERROR_INVALID_HANDLE as i32));
+
+        // Don't try to close the handle again.
+        std::mem::forget(process);
+    }
+
+    #[test]
+    fn thread_close_failure_returns_self() {
+        let thread = unsafe { Thread(Handle::from_raw(INVALID_HANDLE as _)) };
+        let (thread, err) = thread.close().expect_err("closed an invalid handle");
+        assert_eq!(thread.0.as_raw() as usize, INVALID_HANDLE);
+        assert_eq!(err.raw_os_error(), Some(ERROR_INVALID_HANDLE as i32));
+
+        // Don't try to close the handle again.
+        std::mem::forget(thread);
+    }
+
+    #[test]
+    fn thread_exit_code() {
+        const EXIT_CODE: DWORD = 42;
+
+        unsafe extern "system" fn worker(_param: LPVOID) -> DWORD {
+            EXIT_CODE
+        }
+
+        let handle = unsafe {
+            CreateThread(
+                std::ptr::null_mut(),
+                0,
+                Some(worker),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        assert!(!handle.is_null(), "failed to create thread");
+
+        let thread = unsafe { Thread(Handle::from_raw(handle.cast())) };
+        let result = thread.wait(u32::MAX).expect("failed to wait for thread");
+        assert_eq!(result, WaitResult::Signaled);
+        let exit_code = thread.exit_code().expect("failed to get exit code");
+        assert_eq!(exit_code, EXIT_CODE);
+    }
+
+    #[test]
+    fn thread_queue_user_apc() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::atomic::Ordering;
+
+        static APC_DATA: AtomicUsize = AtomicUsize::new(0);
+
+        unsafe extern "system" fn apc(data: usize) {
+            APC_DATA.store(data, Ordering::SeqCst);
+        }
+
+        unsafe extern "system" fn worker(_param: LPVOID) -> DWORD {
+            // Sleep alertably until the APC runs.
+            SleepEx(INFINITE, TRUE)
+        }
+
+        let handle = unsafe {
+            CreateThread(
+                std::ptr::null_mut(),
+                0,
+                Some(worker),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        assert!(!handle.is_null(), "failed to create thread");
+
+        let thread = unsafe { Thread(Handle::from_raw(handle.cast())) };
+        unsafe {
+            thread
+                .queue_user_apc(apc as unsafe extern "system" fn(usize) as usize, 42)
+                .expect("failed to queue apc");
+        }
+        thread.wait(u32::MAX).expect("failed to wait for thread");
+        assert_eq!(APC_DATA.load(Ordering::SeqCst), 42);
+    }
+
+    /// Get the total CPU time used by a process, in 100-nanosecond intervals.
+    fn cpu_time(process: &Process) -> u64 {
+        let mut creation = unsafe { std::mem::zeroed() };
+        let mut exit = unsafe { std::mem::zeroed() };
+        let mut kernel = unsafe { std::mem::zeroed() };
+        let mut user = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            GetProcessTimes(
+                process.0.as_raw().cast(),
+                &mut creation,
+                &mut exit,
+                &mut kernel,
+                &mut user,
+            )
+        };
+        assert!(ret != FALSE, "failed to get process times");
+
+        let to_u64 = |time: winapi::shared::minwindef::FILETIME| {
+            (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime)
+        };
+        to_u64(kernel) + to_u64(user)
+    }
+
+    #[test]
+    fn freeze_thaw() {
+        let mut child = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-Command", "while ($true) {}"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("failed to spawn child");
+
+        let process = Process::open(
+            ProcessAccessRights::SUSPEND_RESUME | ProcessAccessRights::QUERY_LIMITED_INFORMATION,
+            child.id(),
+        )
+        .expect("failed to open child");
+
+        // Let the child start spinning.
+        std::thread::sleep(std::time::Duration::from_secs(2));
+
+        process.freeze().expect("failed to freeze");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let frozen_start = cpu_time(&process);
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let frozen_end = cpu_time(&process);
+        assert_eq!(
+            frozen_start, frozen_end,
+            "the child made progress while frozen"
+        );
+
+        process.thaw().expect("failed to thaw");
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let thawed = cpu_time(&process);
+        assert!(
+            thawed > frozen_end,
+            "the child did not make progress after thawing"
+        );
+
+        child.kill().expect("failed to kill child");
+        child.wait().expect("failed to wait for child");
+    }
+
+    #[test]
+    fn terminate_graceful_console() {
+        let mut child = std::process::Command::new("ping")
+            .args(["-n", "30", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("failed to spawn child");
+
+        let process = Process::open(
+            ProcessAccessRights::QUERY_LIMITED_INFORMATION
+                | ProcessAccessRights::SYNCHRONIZE
+                | ProcessAccessRights::TERMINATE,
+            child.id(),
+        )
+        .expect("failed to open child");
+
+        let terminated = process
+            .terminate_graceful(500, 42)
+            .expect("failed to terminate child");
+        assert!(terminated);
+
+        let status = child.wait().expect("failed to wait for child");
+        assert_eq!(status.code(), Some(42));
+    }
+
+    #[test]
+    fn kill_and_wait() {
+        let mut child = std::process::Command::new("ping")
+            .args(["-n", "30", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("failed to spawn child");
+
+        let process = Process::open(
+            ProcessAccessRights::SYNCHRONIZE | ProcessAccessRights::TERMINATE,
+            child.id(),
+        )
+        .expect("failed to open child");
+
+        let result = process
+            .kill_and_wait(42, 5_000)
+            .expect("failed to kill child");
+        assert_eq!(result, WaitResult::Signaled);
+
+        // The child has already exited, so this does not need to wait.
+        let status = child
+            .try_wait()
+            .expect("failed to get child status")
+            .expect("child is still running");
+        assert_eq!(status.code(), Some(42));
+    }
+
+    #[test]
+    fn cancel_synchronous_io_without_io() {
+        let thread = Thread::open(ThreadAccessRights::TERMINATE, unsafe {
+            GetCurrentThreadId()
+        })
+        .expect("failed to open current thread");
+
+        let cancelled = thread
+            .cancel_synchronous_io()
+            .expect("failed to cancel synchronous io");
+        assert!(!cancelled);
+    }
+
+    #[test]
+    fn debug_attach_detach() {
+        let mut child = std::process::Command::new("ping")
+            .args(["-n", "30", "127.0.0.1"])
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .expect("failed to spawn child");
+
+        let process = Process::open(ProcessAccessRights::QUERY_LIMITED_INFORMATION, child.id())
+            .expect("failed to open child");
+        assert_eq!(process.pid().expect("failed to get pid"), child.id());
+
+        match process.debug_attach() {
+            Ok(()) => {
+                process.debug_detach().expect("failed to detach");
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {}
+            Err(e) => panic!("failed to attach: {}", e),
+        }
+
+        child.kill().expect("failed to kill child");
+        child.wait().expect("failed to wait for child");
+    }
+
+    #[test]
+    fn handle_count() {
+        let process = Process::open(ProcessAccessRights::QUERY_LIMITED_INFORMATION, unsafe {
+            GetCurrentProcessId()
+        })
+        .expect("failed to open current process");
+
+        let before = process.handle_count().expect("failed to get handle count");
+
+        let event = unsafe { CreateEventW(std::ptr::null_mut(), FALSE, FALSE, std::ptr::null()) };
+        assert!(!event.is_null(), "failed to create event");
+        let event = unsafe { Handle::from_raw(event.cast()) };
+
+        // Other threads may open and close handles, so only check that the count did not drop.
+        let after = process.handle_count().expect("failed to get handle count");
+        assert!(after >= before, "{} < {}", after, before);
+        dbg!(before, after);
+
+        drop(event);
+    }
+
+    #[test]
+    fn process_shutdown_parameters() {
+        let (level, flags) =
+            get_process_shutdown_parameters().expect("failed to get shutdown parameters");
+        dbg!(level, flags);
+    }
+
+    #[test]
+    fn enum_process_ids_contains_current() {
+        let pids = enum_process_ids().expect("failed to enumerate processes");
+        assert!(pids.contains(&std::process::id()));
+    }
+
+    #[test]
+    fn base_name() {
+        let process = Process::open(
+            ProcessAccessRights::QUERY_INFORMATION | ProcessAccessRights::VM_READ,
+            std::process::id(),
+        )
+        .expect("failed to open current process");
+
+        let base_name = process.base_name().expect("failed to get base name");
+        let current_exe = std::env::current_exe().expect("failed to get current exe");
+        assert_eq!(Some(base_name.as_os_str()), current_exe.file_name());
+    }
+
+    #[test]
+    fn memory_info() {
+        let process = Process::open(
+            ProcessAccessRights::QUERY_LIMITED_INFORMATION,
+            std::process::id(),
+        )
+        .expect("failed to open current process");
+
+        let info = process.memory_info().expect("failed to get memory info");
+        assert!(info.working_set_size > 0);
+        assert!(info.peak_working_set_size >= info.working_set_size);
+        dbg!(info);
+    }
+
+    #[test]
+    fn dep_policy() {
+        let process = Process::open(ProcessAccessRights::QUERY_INFORMATION, unsafe {
+            GetCurrentProcessId()
+        })
+        .expect("failed to open current process");
+
+        match process.dep_policy() {
+            Ok(policy) => {
+                dbg!(policy);
+            }
+            // DEP policies do not apply to 64-bit processes.
+            Err(e)
+                if cfg!(target_pointer_width = "64")
+                    && (e.raw_os_error() == Some(ERROR_NOT_SUPPORTED as i32)
+                        || e.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32)) => {}
+            Err(e) => panic!("failed to get dep policy: {}", e),
+        }
+    }
+
+    #[test]
+    fn priority_boost() {
+        let process = Process::open(ProcessAccessRights::QUERY_LIMITED_INFORMATION, unsafe {
+            GetCurrentProcessId()
+        })
+        .expect("failed to open current process");
+
+        let disabled = process
+            .priority_boost()
+            .expect("failed to get priority boost");
+        dbg!(disabled);
+    }
+
+    #[test]
+    fn open_all_current_process() {
+        let pid = unsafe { GetCurrentProcessId() };
+        let process = Process::open_all(pid).expect("failed to open current process");
+        assert_eq!(process.pid().expect("failed to get pid"), pid);
+    }
+
+    #[test]
+    fn process_and_thread_are_send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+
+        assert_send::<Process>();
+        assert_sync::<Process>();
+        assert_send::<Thread>();
+        assert_sync::<Thread>();
     }
 }
@@ -1,4 +1,7 @@
+use crate::objbase::create_instance;
+use crate::objbase::format_guid;
 use crate::objbase::CoTaskMemWideString;
+use crate::HResult;
 use std::convert::TryInto;
 use std::ffi::OsString;
 use std::mem::MaybeUninit;
@@ -7,16 +10,38 @@ use std::path::PathBuf;
 use std::ptr::NonNull;
 use winapi::ctypes::c_int;
 use winapi::shared::guiddef::GUID;
+use winapi::shared::minwindef::DWORD;
 use winapi::shared::minwindef::FALSE;
 use winapi::shared::minwindef::MAX_PATH;
 use winapi::shared::minwindef::TRUE;
+use winapi::shared::winerror::ERROR_PATH_NOT_FOUND;
+use winapi::shared::winerror::FAILED;
+use winapi::shared::winerror::S_FALSE;
 use winapi::shared::winerror::S_OK;
+use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+use winapi::um::combaseapi::CoTaskMemFree;
 use winapi::um::knownfolders::FOLDERID_Desktop;
 use winapi::um::knownfolders::FOLDERID_LocalAppData;
+use winapi::um::knownfolders::FOLDERID_SavedGames;
+use winapi::um::shlobj::SHGetFolderPathW;
 use winapi::um::shlobj::SHGetKnownFolderPath;
 use winapi::um::shlobj::SHGetSpecialFolderPathW;
+use winapi::um::shlobj::CSIDL_APPDATA;
 use winapi::um::shlobj::CSIDL_DESKTOP;
+use winapi::um::shlobj::CSIDL_LOCAL_APPDATA;
+use winapi::um::shlobj::CSIDL_PERSONAL;
+use winapi::um::shlobj::CSIDL_PROFILE;
+use winapi::um::shlobj::KF_FLAG_CREATE;
+use winapi::um::shlobj::KF_FLAG_DEFAULT_PATH;
+use winapi::um::shlobj::KF_FLAG_DONT_UNEXPAND;
+use winapi::um::shlobj::KF_FLAG_DONT_VERIFY;
+use winapi::um::shlobj::KF_FLAG_INIT;
+use winapi::um::shlobj::KF_FLAG_NO_ALIAS;
+use winapi::um::shlobj::SHGFP_TYPE_CURRENT;
+use winapi::um::shlobj::SHGFP_TYPE_DEFAULT;
+use winapi::um::shobjidl_core::KnownFolderManager;
 use winapi::um::winbase::lstrlenW;
+use winapi::Class;
 
 /// A folder type
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -27,6 +52,9 @@ pub enum FolderId {
 
     /// The folder that is a "data repository for local (nonroaming) applications"
     LocalAppData,
+
+    /// The current user's saved games folder
+    SavedGames,
 }
 
 impl From<FolderId> for GUID {
@@ -34,21 +62,73 @@ impl From<FolderId> for GUID {
         match folder_id {
             FolderId::Desktop => FOLDERID_Desktop,
             FolderId::LocalAppData => FOLDERID_LocalAppData,
+            FolderId::SavedGames => FOLDERID_SavedGames,
         }
     }
 }
 
+bitflags::bitflags! {
+    /// Flags that control how a known folder path is retrieved.
+    ///
+    pub struct KnownFolderFlags: DWORD {
+        /// Create the folder if it does not exist.
+        ///
+        const CREATE = KF_FLAG_CREATE;
+
+        /// Do not verify that the folder exists before returning the path.
+        ///
+        const DONT_VERIFY = KF_FLAG_DONT_VERIFY;
+
+        /// Initialize the folder with its `desktop.ini` settings.
+        ///
+        const INIT = KF_FLAG_INIT;
+
+        /// Return the non-aliased path.
+        ///
+        const NO_ALIAS = KF_FLAG_NO_ALIAS;
+
+        /// Do not expand environment variables in the returned path.
+        ///
+        const DONT_UNEXPAND = KF_FLAG_DONT_UNEXPAND;
+
+        /// Return the default path instead of any redirected path.
+        ///
+        const DEFAULT_PATH = KF_FLAG_DEFAULT_PATH;
+    }
+}
+
 /// Get a known folder path.
 ///
+/// Pass [`KnownFolderFlags::empty()`] for the default behavior.
+///
+/// # Errors
+/// * Returns an error if the path could not be retrieved.
+///
+/// # Panics
+/// * Panics if the operation was successful, yet the path pointer is still null.
+pub fn get_known_folder_path(
+    folder_id: FolderId,
+    flags: KnownFolderFlags,
+) -> std::io::Result<CoTaskMemWideString> {
+    get_known_folder_path_by_guid(&folder_id.into(), flags)
+}
+
+/// Get a known folder path from a raw `KNOWNFOLDERID`.
+///
+/// This allows retrieving folders that are not covered by [`FolderId`], like third-party known folders.
+///
 /// # Errors
 /// * Returns an error if the path could not be retrieved.
 ///
 /// # Panics
 /// * Panics if the operation was successful, yet the path pointer is still null.
-pub fn get_known_folder_path(folder_id: FolderId) -> std::io::Result<CoTaskMemWideString> {
-    let folder_id: GUID = folder_id.into();
+pub fn get_known_folder_path_by_guid(
+    guid: &GUID,
+    flags: KnownFolderFlags,
+) -> std::io::Result<CoTaskMemWideString> {
     let mut path_ptr = std::ptr::null_mut();
-    let ret = unsafe { SHGetKnownFolderPath(&folder_id, 0, std::ptr::null_mut(), &mut path_ptr) };
+    let ret =
+        unsafe { SHGetKnownFolderPath(guid, flags.bits(), std::ptr::null_mut(), &mut path_ptr) };
     let path = NonNull::new(path_ptr).map(|ptr| unsafe { CoTaskMemWideString::from_raw(ptr) });
 
     if ret != S_OK {
@@ -58,17 +138,169 @@ pub fn get_known_folder_path(folder_id: FolderId) -> std::io::Result<CoTaskMemWi
     Ok(path.expect("path ptr was null"))
 }
 
+/// Get a known folder path as a [`PathBuf`].
+///
+/// This allocates a new [`PathBuf`]. Use [`get_known_folder_path`] to avoid the copy.
+///
+/// # Errors
+/// * Returns an error if the path could not be retrieved.
+pub fn get_known_folder_path_buf(
+    folder_id: FolderId,
+    flags: KnownFolderFlags,
+) -> std::io::Result<PathBuf> {
+    get_known_folder_path(folder_id, flags).map(|path| path.as_os_string().into())
+}
+
+// `IKnownFolderManager` is not exposed by `winapi`.
+// Only the methods up to the ones used here are declared, as the rest of the vtable is never accessed.
+#[allow(non_snake_case)]
+mod ffi {
+    use winapi::ctypes::c_int;
+    use winapi::shared::minwindef::UINT;
+    use winapi::shared::winerror::HRESULT;
+    use winapi::um::shtypes::KNOWNFOLDERID;
+    use winapi::um::shtypes::REFKNOWNFOLDERID;
+    use winapi::um::unknwnbase::IUnknown;
+    use winapi::um::unknwnbase::IUnknownVtbl;
+    use winapi::RIDL;
+
+    RIDL! {#[uuid(0x8be2d872, 0x86aa, 0x4d47, 0xb7, 0x76, 0x32, 0xcc, 0xa4, 0x0c, 0x70, 0x18)]
+    interface IKnownFolderManager(IKnownFolderManagerVtbl): IUnknown(IUnknownVtbl) {
+        fn FolderIdFromCsidl(
+            nCsidl: c_int,
+            pfid: *mut KNOWNFOLDERID,
+        ) -> HRESULT,
+        fn FolderIdToCsidl(
+            rfid: REFKNOWNFOLDERID,
+            pnCsidl: *mut c_int,
+        ) -> HRESULT,
+        fn GetFolderIds(
+            ppKFId: *mut *mut KNOWNFOLDERID,
+            pCount: *mut UINT,
+        ) -> HRESULT,
+    }}
+}
+
+/// A known folder registered on this system, as returned by [`enum_known_folders`].
+pub struct KnownFolderInfo {
+    /// The `KNOWNFOLDERID` of this folder
+    pub id: GUID,
+
+    /// The path of this folder, or `None` if it could not be resolved.
+    ///
+    /// Virtual folders, like the Control Panel, do not have a path.
+    pub path: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for KnownFolderInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KnownFolderInfo")
+            .field("id", &format_guid(&self.id))
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Enumerate the known folders registered on this system, using `IKnownFolderManager::GetFolderIds`.
+///
+/// COM must be initialized on this thread, like with [`crate::init_mta_com_runtime`].
+/// Paths are resolved on a best-effort basis, so folders that fail to resolve are included with a `None` path.
+///
+/// # Errors
+/// * Returns an error if the known folder manager could not be created or the folder ids could not be retrieved.
+pub fn enum_known_folders() -> std::io::Result<Vec<KnownFolderInfo>> {
+    let manager = unsafe {
+        create_instance::<ffi::IKnownFolderManager>(
+            &KnownFolderManager::uuidof(),
+            CLSCTX_INPROC_SERVER,
+        )
+    }?;
+
+    let mut ids_ptr = std::ptr::null_mut();
+    let mut count = 0;
+
+    // # Safety
+    // `manager` is a valid `IKnownFolderManager`, which is released right after the call.
+    let ret = unsafe {
+        let ret = (*manager).GetFolderIds(&mut ids_ptr, &mut count);
+        (*manager).Release();
+        ret
+    };
+
+    if FAILED(ret) {
+        return Err(HResult::from(ret).into());
+    }
+
+    if ids_ptr.is_null() {
+        return Ok(Vec::new());
+    }
+
+    // # Safety
+    // On success, `ids_ptr` points to `count` ids allocated with `CoTaskMemAlloc`, which are copied out before it is freed.
+    let ids = unsafe {
+        let ids = std::slice::from_raw_parts(ids_ptr, count as usize).to_vec();
+        CoTaskMemFree(ids_ptr.cast());
+        ids
+    };
+
+    Ok(ids
+        .into_iter()
+        .map(|id| {
+            let path = get_known_folder_path_by_guid(&id, KnownFolderFlags::empty())
+                .ok()
+                .map(|path| path.as_os_string().into());
+
+            KnownFolderInfo { id, path }
+        })
+        .collect())
+}
+
 /// The location of a folder
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ConstantSpecialItemIdList {
     /// The desktop
     Desktop,
+
+    /// The current user's roaming application data folder
+    AppData,
+
+    /// The current user's local (nonroaming) application data folder
+    LocalAppData,
+
+    /// The current user's documents folder
+    Personal,
+
+    /// The current user's profile folder
+    Profile,
 }
 
 impl From<ConstantSpecialItemIdList> for c_int {
     fn from(csidl: ConstantSpecialItemIdList) -> c_int {
         match csidl {
             ConstantSpecialItemIdList::Desktop => CSIDL_DESKTOP,
+            ConstantSpecialItemIdList::AppData => CSIDL_APPDATA,
+            ConstantSpecialItemIdList::LocalAppData => CSIDL_LOCAL_APPDATA,
+            ConstantSpecialItemIdList::Personal => CSIDL_PERSONAL,
+            ConstantSpecialItemIdList::Profile => CSIDL_PROFILE,
+        }
+    }
+}
+
+/// Which path [`get_folder_path`] should return.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SHGetFolderFlags {
+    /// The current path of the folder, which may have been redirected
+    Current,
+
+    /// The default path of the folder
+    Default,
+}
+
+impl From<SHGetFolderFlags> for DWORD {
+    fn from(flags: SHGetFolderFlags) -> Self {
+        match flags {
+            SHGetFolderFlags::Current => SHGFP_TYPE_CURRENT,
+            SHGetFolderFlags::Default => SHGFP_TYPE_DEFAULT,
         }
     }
 }
@@ -119,6 +351,60 @@ pub fn get_special_folder_path(
     Some(OsString::from_wide(buffer).into())
 }
 
+/// Get a folder path from a csidl using `SHGetFolderPathW`.
+///
+/// Note: This function is considered legacy, but is more reliable than [`get_special_folder_path`].
+/// Prefer [`get_known_folder_path`] where possible.
+///
+/// # Errors
+/// * Returns an error if the csidl path could not be located.
+/// * Returns an error with the code `ERROR_PATH_NOT_FOUND` if the csidl is valid, but its folder does not exist.
+pub fn get_folder_path(
+    csidl: ConstantSpecialItemIdList,
+    flags: SHGetFolderFlags,
+) -> std::io::Result<PathBuf> {
+    const BUFFER_LEN: usize = MAX_PATH + 1;
+
+    let mut buffer = MaybeUninit::<[u16; BUFFER_LEN]>::uninit();
+    let csidl: c_int = csidl.into();
+
+    // # Safety
+    // The buffer exists and has a minimum length of MAX_PATH.
+    let ret = unsafe {
+        SHGetFolderPathW(
+            std::ptr::null_mut(),
+            csidl,
+            std::ptr::null_mut(),
+            flags.into(),
+            buffer.as_mut_ptr().cast(),
+        )
+    };
+
+    // `S_FALSE` is returned if the csidl is valid, but the folder does not exist.
+    if ret == S_FALSE {
+        return Err(std::io::Error::from_raw_os_error(
+            ERROR_PATH_NOT_FOUND as i32,
+        ));
+    }
+
+    if ret != S_OK {
+        return Err(HResult::from(ret).into());
+    }
+
+    // # Safety
+    // The data must be valid at this point.
+    // The data is NUL terminated.
+    // There are only immutable references left to `buffer`, so making another immutable one is safe.
+    let buffer = unsafe {
+        let len: usize = lstrlenW(buffer.as_ptr().cast())
+            .try_into()
+            .expect("could not convert string length into a `usize`");
+        std::slice::from_raw_parts(buffer.as_ptr().cast(), len)
+    };
+
+    Ok(OsString::from_wide(buffer).into())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -132,10 +418,69 @@ mod test {
 
     #[test]
     fn get_known_folder_path_smoke() {
-        let desktop = get_known_folder_path(FolderId::Desktop).expect("failed to get desktop");
+        let desktop = get_known_folder_path(FolderId::Desktop, KnownFolderFlags::empty())
+            .expect("failed to get desktop");
         dbg!(desktop);
         let local_app_data =
-            get_known_folder_path(FolderId::LocalAppData).expect("failed to get local_app_data");
+            get_known_folder_path(FolderId::LocalAppData, KnownFolderFlags::empty())
+                .expect("failed to get local_app_data");
         dbg!(local_app_data);
     }
+
+    #[test]
+    fn get_known_folder_path_buf_smoke() {
+        let desktop = get_known_folder_path_buf(FolderId::Desktop, KnownFolderFlags::empty())
+            .expect("failed to get desktop");
+        assert!(desktop.exists());
+    }
+
+    #[test]
+    fn get_known_folder_path_create() {
+        let saved_games = get_known_folder_path_buf(FolderId::SavedGames, KnownFolderFlags::CREATE)
+            .expect("failed to get saved_games");
+        assert!(saved_games.exists());
+    }
+
+    #[test]
+    fn get_known_folder_path_by_guid_smoke() {
+        let desktop_guid =
+            get_known_folder_path_by_guid(&FOLDERID_Desktop, KnownFolderFlags::empty())
+                .expect("failed to get desktop");
+        let desktop = get_known_folder_path(FolderId::Desktop, KnownFolderFlags::empty())
+            .expect("failed to get desktop");
+        assert_eq!(desktop_guid.as_os_string(), desktop.as_os_string());
+    }
+
+    #[test]
+    fn get_folder_path_smoke() {
+        let csidls = [
+            ConstantSpecialItemIdList::Desktop,
+            ConstantSpecialItemIdList::AppData,
+            ConstantSpecialItemIdList::LocalAppData,
+            ConstantSpecialItemIdList::Personal,
+            ConstantSpecialItemIdList::Profile,
+        ];
+
+        for csidl in csidls.iter().copied() {
+            let path = get_folder_path(csidl, SHGetFolderFlags::Current)
+                .unwrap_or_else(|e| panic!("failed to get {:?}: {}", csidl, e));
+            assert!(path.exists(), "{:?} does not exist", csidl);
+
+            get_folder_path(csidl, SHGetFolderFlags::Default)
+                .unwrap_or_else(|e| panic!("failed to get default {:?}: {}", csidl, e));
+        }
+    }
+
+    #[test]
+    fn enum_known_folders_contains_desktop() {
+        crate::init_mta_com_runtime().expect("failed to init COM runtime");
+
+        let folders = enum_known_folders().expect("failed to enum known folders");
+        let desktop_id: GUID = FolderId::Desktop.into();
+        let desktop = folders
+            .iter()
+            .find(|folder| format_guid(&folder.id) == format_guid(&desktop_id))
+            .expect("missing desktop");
+        assert!(desktop.path.is_some());
+    }
 }
@@ -0,0 +1,374 @@
+use crate::handleapi::Handle;
+use crate::handleapi::OwnedHandle;
+use crate::handleapi::WaitResult;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::minwindef::TRUE;
+use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::synchapi::CreateEventW;
+use winapi::um::synchapi::CreateMutexW;
+use winapi::um::synchapi::OpenEventW;
+use winapi::um::synchapi::ReleaseMutex;
+use winapi::um::synchapi::ResetEvent;
+use winapi::um::synchapi::SetEvent;
+use winapi::um::winbase::PulseEvent;
+use winapi::um::winnt::EVENT_MODIFY_STATE;
+use winapi::um::winnt::HANDLE;
+use winapi::um::winnt::SYNCHRONIZE;
+
+/// An Event
+///
+/// A manual-reset event stays signaled until it is reset,
+/// while an auto-reset event is reset when a single waiting thread is released.
+#[derive(Debug)]
+pub struct Event(Handle);
+
+impl Event {
+    /// Create a new unnamed [`Event`].
+    ///
+    /// # Errors
+    /// Returns an error if the event could not be created.
+    ///
+    pub fn new(manual_reset: bool, initial_state: bool) -> std::io::Result<Self> {
+        Self::create(None, manual_reset, initial_state)
+    }
+
+    /// Create a new named [`Event`], which can be opened by other processes with [`Event::open`].
+    ///
+    /// If an event with this name already exists, it is opened instead and the other arguments are ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the event could not be created.
+    ///
+    pub fn new_named(
+        name: &OsStr,
+        manual_reset: bool,
+        initial_state: bool,
+    ) -> std::io::Result<Self> {
+        Self::create(Some(name), manual_reset, initial_state)
+    }
+
+    fn create(
+        name: Option<&OsStr>,
+        manual_reset: bool,
+        initial_state: bool,
+    ) -> std::io::Result<Self> {
+        let name: Option<Vec<u16>> =
+            name.map(|name| name.encode_wide().chain(std::iter::once(0)).collect());
+        let handle = unsafe {
+            CreateEventW(
+                std::ptr::null_mut(),
+                if manual_reset { TRUE } else { FALSE },
+                if initial_state { TRUE } else { FALSE },
+                name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr()),
+            )
+        };
+
+        Self::from_raw_result(handle)
+    }
+
+    /// Open an existing named [`Event`].
+    ///
+    /// The event is opened with the rights to set, reset, and wait on it.
+    ///
+    /// # Errors
+    /// Returns an error if the event could not be opened, like if it does not exist.
+    ///
+    pub fn open(name: &OsStr) -> std::io::Result<Self> {
+        let name: Vec<u16> = name.encode_wide().chain(std::iter::once(0)).collect();
+        let handle = unsafe { OpenEventW(EVENT_MODIFY_STATE | SYNCHRONIZE, FALSE, name.as_ptr()) };
+
+        Self::from_raw_result(handle)
+    }
+
+    /// Make an [`Event`] from the return value of `CreateEventW` or `OpenEventW`.
+    fn from_raw_result(handle: HANDLE) -> std::io::Result<Self> {
+        if handle.is_null() {
+            Err(std::io::Error::last_os_error())
+        } else {
+            unsafe { Ok(Self(Handle::from_raw(handle.cast()))) }
+        }
+    }
+
+    /// Get the [`Handle`] of this event.
+    ///
+    pub fn as_handle(&self) -> &Handle {
+        &self.0
+    }
+
+    /// Set this event to the signaled state.
+    ///
+    /// # Errors
+    /// Returns an error if the event could not be set.
+    ///
+    pub fn set(&self) -> std::io::Result<()> {
+        let ret = unsafe { SetEvent(self.0.as_raw().cast()) };
+
+        if ret == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Set this event to the nonsignaled state.
+    ///
+    /// # Errors
+    /// Returns an error if the event could not be reset.
+    ///
+    pub fn reset(&self) -> std::io::Result<()> {
+        let ret = unsafe { ResetEvent(self.0.as_raw().cast()) };
+
+        if ret == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Set this event to the signaled state, release waiting threads, then reset it.
+    ///
+    /// This is unreliable, as a waiting thread that is temporarily removed from the wait, like by an APC, will miss the pulse.
+    /// Prefer [`Event::set`] where possible.
+    ///
+    /// # Errors
+    /// Returns an error if the event could not be pulsed.
+    ///
+    pub fn pulse(&self) -> std::io::Result<()> {
+        let ret = unsafe { PulseEvent(self.0.as_raw().cast()) };
+
+        if ret == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Wait for this event to be signaled,
+    /// until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
+    ///
+    /// # Errors
+    /// Returns an error if this event could not be waited on.
+    ///
+    pub fn wait(&self, millis: u32) -> std::io::Result<WaitResult> {
+        self.0.wait(millis)
+    }
+
+    /// Try to close this [`Event`].
+    ///
+    /// # Errors
+    /// Returns an error which contains this object if this object could not be destroyed.
+    ///
+    pub fn close(self) -> Result<(), (Self, std::io::Error)> {
+        OwnedHandle::close(self)
+    }
+}
+
+impl OwnedHandle for Event {
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn into_handle(self) -> Handle {
+        self.0
+    }
+}
+
+/// A Mutex
+///
+/// A mutex is owned by the thread that acquired it, and must be released by that thread.
+/// Named mutexes can be used for cross-process synchronization, like making sure only one instance of an app is running.
+#[derive(Debug)]
+pub struct Mutex(Handle);
+
+impl Mutex {
+    /// Create a new unnamed [`Mutex`], which is not owned by any thread.
+    ///
+    /// # Errors
+    /// Returns an error if the mutex could not be created.
+    ///
+    pub fn new() -> std::io::Result<Self> {
+        Self::create(None).map(|(mutex, _)| mutex)
+    }
+
+    /// Create a new named [`Mutex`], which is not owned by any thread.
+    ///
+    /// If a mutex with this name already exists, it is opened instead.
+    /// Returns the mutex and whether it already existed.
+    ///
+    /// # Errors
+    /// Returns an error if the mutex could not be created.
+    ///
+    pub fn new_named(name: &OsStr) -> std::io::Result<(Self, bool)> {
+        Self::create(Some(name))
+    }
+
+    fn create(name: Option<&OsStr>) -> std::io::Result<(Self, bool)> {
+        let name: Option<Vec<u16>> =
+            name.map(|name| name.encode_wide().chain(std::iter::once(0)).collect());
+        let handle = unsafe {
+            CreateMutexW(
+                std::ptr::null_mut(),
+                FALSE,
+                name.as_ref().map_or(std::ptr::null(), |name| name.as_ptr()),
+            )
+        };
+
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // The last error is set even on success if the mutex already existed.
+        let already_existed = unsafe { GetLastError() } == ERROR_ALREADY_EXISTS;
+
+        unsafe { Ok((Self(Handle::from_raw(handle.cast())), already_existed)) }
+    }
+
+    /// Get the [`Handle`] of this mutex.
+    ///
+    pub fn as_handle(&self) -> &Handle {
+        &self.0
+    }
+
+    /// Wait for this mutex to be released and take ownership of it,
+    /// until the given interval elapses, immediately if it is 0, and indefinitely if it is `u32::MAX`.
+    ///
+    /// If this returns [`WaitResult::Signaled`] or [`WaitResult::Abandoned`], the calling thread owns the mutex.
+    /// [`WaitResult::Abandoned`] means the previous owner exited without releasing it,
+    /// so the data it protects may be in an inconsistent state.
+    ///
+    /// # Errors
+    /// Returns an error if this mutex could not be waited on.
+    ///
+    pub fn acquire(&self, millis: u32) -> std::io::Result<WaitResult> {
+        self.0.wait(millis)
+    }
+
+    /// Release ownership of this mutex.
+    ///
+    /// # Errors
+    /// Returns an error if the mutex could not be released, like if the calling thread does not own it.
+    ///
+    pub fn release(&self) -> std::io::Result<()> {
+        let ret = unsafe { ReleaseMutex(self.0.as_raw().cast()) };
+
+        if ret == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Try to close this [`Mutex`].
+    ///
+    /// # Errors
+    /// Returns an error which contains this object if this object could not be destroyed.
+    ///
+    pub fn close(self) -> Result<(), (Self, std::io::Error)> {
+        OwnedHandle::close(self)
+    }
+}
+
+impl OwnedHandle for Mutex {
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn into_handle(self) -> Handle {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn event_set_and_wait() {
+        let event = Event::new(true, false).expect("failed to create event");
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Timeout);
+
+        event.set().expect("failed to set event");
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Signaled);
+        // Manual-reset events stay signaled.
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Signaled);
+
+        event.reset().expect("failed to reset event");
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Timeout);
+
+        // No threads are waiting, so a pulse leaves the event reset.
+        event.pulse().expect("failed to pulse event");
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Timeout);
+    }
+
+    #[test]
+    fn event_auto_reset() {
+        let event = Event::new(false, true).expect("failed to create event");
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Signaled);
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Timeout);
+    }
+
+    #[test]
+    fn event_open_named() {
+        let name = format!("skylight-event-open-named-{}", std::process::id());
+        let event = Event::new_named(name.as_ref(), true, false).expect("failed to create event");
+        let opened = Event::open(name.as_ref()).expect("failed to open event");
+
+        opened.set().expect("failed to set event");
+        assert_eq!(event.wait(0).expect("failed to wait"), WaitResult::Signaled);
+
+        drop(event);
+        drop(opened);
+        assert!(Event::open(name.as_ref()).is_err());
+    }
+
+    #[test]
+    fn mutex_named_already_exists() {
+        let name = format!("skylight-mutex-already-exists-{}", std::process::id());
+        let (first, already_existed) =
+            Mutex::new_named(name.as_ref()).expect("failed to create mutex");
+        assert!(!already_existed);
+
+        let (second, already_existed) =
+            Mutex::new_named(name.as_ref()).expect("failed to create mutex");
+        assert!(already_existed);
+
+        drop(first);
+        drop(second);
+
+        let (_third, already_existed) =
+            Mutex::new_named(name.as_ref()).expect("failed to create mutex");
+        assert!(!already_existed);
+    }
+
+    #[test]
+    fn mutex_acquire_release() {
+        let mutex = Mutex::new().expect("failed to create mutex");
+
+        // Releasing a mutex that is not owned fails.
+        assert!(mutex.release().is_err());
+
+        assert_eq!(
+            mutex.acquire(0).expect("failed to acquire mutex"),
+            WaitResult::Signaled
+        );
+        mutex.release().expect("failed to release mutex");
+
+        // Another thread cannot acquire the mutex while it is owned.
+        let mutex = std::sync::Arc::new(mutex);
+        assert_eq!(
+            mutex.acquire(0).expect("failed to acquire mutex"),
+            WaitResult::Signaled
+        );
+        let result = {
+            let mutex = mutex.clone();
+            std::thread::spawn(move || mutex.acquire(0).expect("failed to acquire mutex"))
+                .join()
+                .expect("thread panicked")
+        };
+        assert_eq!(result, WaitResult::Timeout);
+        mutex.release().expect("failed to release mutex");
+    }
+}
@@ -1,14 +1,21 @@
 use crate::handleapi::Handle;
+use crate::handleapi::OwnedHandle;
+use crate::processthreadsapi::Process;
+use crate::processthreadsapi::ProcessAccessRights;
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
+use std::path::PathBuf;
 use winapi::shared::minwindef::DWORD;
 use winapi::shared::minwindef::TRUE;
 use winapi::um::handleapi::INVALID_HANDLE_VALUE;
 use winapi::um::tlhelp32::CreateToolhelp32Snapshot;
+use winapi::um::tlhelp32::Module32FirstW;
 use winapi::um::tlhelp32::Process32FirstW;
 use winapi::um::tlhelp32::Process32NextW;
+use winapi::um::tlhelp32::MODULEENTRY32W;
 use winapi::um::tlhelp32::PROCESSENTRY32W;
 use winapi::um::tlhelp32::TH32CS_SNAPALL;
+use winapi::um::tlhelp32::TH32CS_SNAPMODULE;
 
 // TODO: Finish Mask
 bitflags::bitflags! {
@@ -16,10 +23,14 @@ bitflags::bitflags! {
     ///
     pub struct SnapshotFlags: DWORD {
         const SNAP_ALL = TH32CS_SNAPALL;
+        const SNAP_MODULE = TH32CS_SNAPMODULE;
     }
 }
 
 /// A Snapshot of process and heap info.
+///
+/// This is `Send` and `Sync`, since the underlying [`Handle`] is.
+/// Iterating requires `&mut self`, so a shared [`Snapshot`] cannot be iterated from multiple threads at once.
 #[derive(Debug)]
 pub struct Snapshot(Handle);
 
@@ -48,13 +59,32 @@ impl Snapshot {
         ProcessIter::from_snapshot(self)
     }
 
+    /// Collect the processes in this snapshot into a [`Vec`].
+    ///
+    /// Unlike [`Snapshot::iter_processes`], the borrow of this snapshot ends when this returns,
+    /// so the processes can be used after the snapshot is dropped.
+    ///
+    pub fn collect_processes(&mut self) -> Vec<ProcessEntry> {
+        self.iter_processes().collect()
+    }
+
     /// Try to close this [`Snapshot`].
     ///
     /// # Errors
     /// Returns an error which contains this object if this object could not be destroyed.
     ///
     pub fn close(self) -> Result<(), (Self, std::io::Error)> {
-        self.0.close().map_err(|(handle, err)| (Self(handle), err))
+        OwnedHandle::close(self)
+    }
+}
+
+impl OwnedHandle for Snapshot {
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn into_handle(self) -> Handle {
+        self.0
     }
 }
 
@@ -142,6 +172,45 @@ impl ProcessEntry {
     pub fn exe_name(&self) -> OsString {
         OsString::from_wide(self.exe_name_wide_slice())
     }
+
+    /// Get the full path of the exe of this process, from the first module of a module snapshot of the process.
+    ///
+    /// # Errors
+    /// Fails if the module snapshot could not be created or is empty.
+    /// This is usually the case for protected processes, which fail with an access denied or `ERROR_PARTIAL_COPY` error.
+    ///
+    pub fn exe_full_path(&self) -> std::io::Result<PathBuf> {
+        let handle = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPMODULE, self.pid()) };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+        let snapshot = Snapshot(unsafe { Handle::from_raw(handle.cast()) });
+
+        let mut entry: MODULEENTRY32W = unsafe { std::mem::zeroed() };
+        entry.dwSize = std::mem::size_of::<MODULEENTRY32W>() as DWORD;
+
+        // The first module is the main image of the process.
+        if unsafe { Module32FirstW(snapshot.0.as_raw().cast(), &mut entry) } != TRUE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let len = entry
+            .szExePath
+            .iter()
+            .position(|el| *el == 0)
+            .unwrap_or(entry.szExePath.len());
+
+        Ok(OsString::from_wide(&entry.szExePath[..len]).into())
+    }
+
+    /// Open the process this [`ProcessEntry`] refers to.
+    ///
+    /// # Errors
+    /// Fails if the process could not be opened, like if it has exited.
+    ///
+    pub fn open(&self, access_rights: ProcessAccessRights) -> std::io::Result<Process> {
+        Process::open(access_rights, self.pid())
+    }
 }
 
 impl std::fmt::Debug for ProcessEntry {
@@ -160,3 +229,78 @@ impl From<PROCESSENTRY32W> for ProcessEntry {
         Self(entry)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use winapi::shared::winerror::ERROR_INVALID_HANDLE;
+
+    #[test]
+    fn snapshot_close_failure_returns_self() {
+        // A handle value that is not open, used to simulate a failing close.
+        const INVALID_HANDLE: usize = 0x7FFF_FFF0;
+
+        let snapshot = unsafe { Snapshot(Handle::from_raw(INVALID_HANDLE as _)) };
+        let (snapshot, err) = snapshot.close().expect_err("closed an invalid handle");
+        assert_eq!(snapshot.0.as_raw() as usize, INVALID_HANDLE);
+        assert_eq!(err.raw_os_error(), Some(ERROR_INVALID_HANDLE as i32));
+
+        // Don't try to close the handle again.
+        std::mem::forget(snapshot);
+    }
+
+    #[test]
+    fn process_entry_open() {
+        let pid = std::process::id();
+        let mut snapshot = Snapshot::new(SnapshotFlags::SNAP_ALL).expect("failed to make snapshot");
+        let entry = snapshot
+            .iter_processes()
+            .find(|entry| entry.pid() == pid)
+            .expect("failed to find current process");
+
+        let process = entry
+            .open(ProcessAccessRights::QUERY_LIMITED_INFORMATION)
+            .expect("failed to open current process");
+        assert_eq!(process.pid().expect("failed to get pid"), pid);
+    }
+
+    #[test]
+    fn process_entry_exe_full_path() {
+        let pid = std::process::id();
+        let mut snapshot = Snapshot::new(SnapshotFlags::SNAP_ALL).expect("failed to make snapshot");
+        let entry = snapshot
+            .iter_processes()
+            .find(|entry| entry.pid() == pid)
+            .expect("failed to find current process");
+
+        let path = entry.exe_full_path().expect("failed to get exe path");
+        assert!(path.is_absolute());
+        assert_eq!(
+            std::fs::canonicalize(path).unwrap(),
+            std::fs::canonicalize(std::env::current_exe().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn snapshot_collect_processes() {
+        let pid = std::process::id();
+        let mut snapshot = Snapshot::new(SnapshotFlags::SNAP_ALL).expect("failed to make snapshot");
+        let processes = snapshot.collect_processes();
+        snapshot
+            .close()
+            .map_err(|(_, e)| e)
+            .expect("failed to close snapshot");
+
+        assert!(!processes.is_empty());
+        assert!(processes.iter().any(|entry| entry.pid() == pid));
+    }
+
+    #[test]
+    fn snapshot_is_send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+
+        assert_send::<Snapshot>();
+        assert_sync::<Snapshot>();
+    }
+}
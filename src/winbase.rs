@@ -1,26 +1,329 @@
 use std::fmt::Write;
 use std::mem::ManuallyDrop;
 use std::mem::MaybeUninit;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
 use std::ptr::NonNull;
-use std::{convert::TryInto, ffi::OsString, os::windows::ffi::OsStringExt};
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use std::{convert::TryInto, ffi::OsStr, ffi::OsString, os::windows::ffi::OsStringExt};
+use winapi::ctypes::c_int;
 use winapi::shared::lmcons::UNLEN;
+use winapi::shared::minwindef::BOOL;
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::minwindef::FILETIME;
+use winapi::shared::minwindef::TRUE;
+use winapi::shared::winerror::ERROR_ENVVAR_NOT_FOUND;
+use winapi::shared::winerror::ERROR_INSUFFICIENT_BUFFER;
+use winapi::shared::winerror::ERROR_MORE_DATA;
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::debugapi::OutputDebugStringW;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::errhandlingapi::SetLastError;
+use winapi::um::heapapi::GetProcessHeap;
+use winapi::um::heapapi::HeapAlloc;
+use winapi::um::heapapi::HeapFree;
+use winapi::um::minwinbase::LPTR;
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::processenv::ExpandEnvironmentStringsW;
+use winapi::um::processenv::GetCurrentDirectoryW;
+use winapi::um::processenv::GetEnvironmentVariableW;
+use winapi::um::processenv::SetCurrentDirectoryW;
+use winapi::um::processenv::SetEnvironmentVariableW;
+use winapi::um::sysinfoapi::ComputerNameDnsDomain;
+use winapi::um::sysinfoapi::ComputerNameDnsFullyQualified;
+use winapi::um::sysinfoapi::ComputerNameDnsHostname;
+use winapi::um::sysinfoapi::ComputerNameNetBIOS;
+use winapi::um::sysinfoapi::ComputerNamePhysicalDnsDomain;
+use winapi::um::sysinfoapi::ComputerNamePhysicalDnsFullyQualified;
+use winapi::um::sysinfoapi::ComputerNamePhysicalDnsHostname;
+use winapi::um::sysinfoapi::ComputerNamePhysicalNetBIOS;
+use winapi::um::sysinfoapi::GetComputerNameExW;
+use winapi::um::sysinfoapi::GetSystemTimeAsFileTime;
+use winapi::um::sysinfoapi::VerSetConditionMask;
+use winapi::um::sysinfoapi::COMPUTER_NAME_FORMAT;
+use winapi::um::timezoneapi::FileTimeToSystemTime;
+use winapi::um::timezoneapi::SystemTimeToFileTime;
 use winapi::um::winbase::lstrlenW;
+use winapi::um::winbase::GetBinaryTypeW;
+use winapi::um::winbase::GetComputerNameW;
 use winapi::um::winbase::GetUserNameW;
+use winapi::um::winbase::LocalAlloc;
 use winapi::um::winbase::LocalFree;
+use winapi::um::winbase::LookupAccountNameW;
+use winapi::um::winbase::VerifyVersionInfoW;
+use winapi::um::winbase::WTSGetActiveConsoleSessionId;
+use winapi::um::winnls::GetSystemDefaultLocaleName;
+use winapi::um::winnls::GetUserDefaultLocaleName;
+use winapi::um::winnt::SidTypeAlias;
+use winapi::um::winnt::SidTypeComputer;
+use winapi::um::winnt::SidTypeDeletedAccount;
+use winapi::um::winnt::SidTypeDomain;
+use winapi::um::winnt::SidTypeGroup;
+use winapi::um::winnt::SidTypeInvalid;
+use winapi::um::winnt::SidTypeLabel;
+use winapi::um::winnt::SidTypeLogonSession;
+use winapi::um::winnt::SidTypeUnknown;
+use winapi::um::winnt::SidTypeUser;
+use winapi::um::winnt::SidTypeWellKnownGroup;
+use winapi::um::winnt::HEAP_ZERO_MEMORY;
+use winapi::um::winnt::LOCALE_NAME_MAX_LENGTH;
+use winapi::um::winnt::OSVERSIONINFOEXW;
+use winapi::um::winnt::SID_NAME_USE;
+use winapi::um::winnt::VER_BUILDNUMBER;
+use winapi::um::winnt::VER_GREATER;
+use winapi::um::winnt::VER_GREATER_EQUAL;
+use winapi::um::winnt::VER_MAJORVERSION;
+use winapi::um::winnt::VER_MINORVERSION;
+
+/// Call a function that fills a wide string buffer, growing the buffer and retrying until it is large enough.
+///
+/// `f` is passed a pointer to the buffer and its length in wide chars.
+/// On success, it must return a nonzero value and set the length to the number of wide chars written, with or without the NUL terminator.
+/// If the buffer is too small, it must fail with `ERROR_MORE_DATA` or `ERROR_INSUFFICIENT_BUFFER`,
+/// and may set the length to the required length.
+///
+/// The returned buffer does not include the NUL terminator.
+fn fill_wide_buffer<F>(initial_len: u32, mut f: F) -> std::io::Result<Vec<u16>>
+where
+    F: FnMut(*mut u16, &mut u32) -> BOOL,
+{
+    let mut buffer: Vec<u16> = Vec::with_capacity(initial_len as usize);
+
+    loop {
+        let capacity: u32 = buffer
+            .capacity()
+            .try_into()
+            .expect("buffer capacity cannot fit in a `u32`");
+        let mut buffer_len = capacity;
+
+        let ret = f(buffer.as_mut_ptr(), &mut buffer_len);
+
+        if ret != 0 {
+            assert!(
+                buffer_len <= capacity,
+                "the length of the data is larger than the buffer"
+            );
+
+            // # Safety
+            // The length of data has been updated and is within the buffer's capacity.
+            unsafe {
+                buffer.set_len(buffer_len as usize);
+            }
+
+            if buffer.last() == Some(&0) {
+                buffer.pop();
+            }
+
+            return Ok(buffer);
+        }
+
+        match unsafe { GetLastError() } {
+            ERROR_MORE_DATA | ERROR_INSUFFICIENT_BUFFER => {}
+            _ => return Err(std::io::Error::last_os_error()),
+        }
+
+        // `buffer_len` usually holds the required length, including the nul terminator.
+        // Not all functions report it, so the buffer is at least doubled in that case.
+        let required = std::cmp::max(buffer_len, capacity.saturating_mul(2).max(1));
+        buffer.reserve(required as usize);
+    }
+}
+
+/// Adapt the return value of a function that returns the length of the data it wrote for [`fill_wide_buffer`].
+///
+/// These functions return 0 on failure, and the required length including the NUL terminator if the buffer is too small.
+/// `includes_nul` is whether the length returned on success includes the NUL terminator.
+fn wide_len_result(ret: u32, buffer_len: &mut u32, includes_nul: bool) -> BOOL {
+    if ret == 0 {
+        return FALSE;
+    }
+
+    let fits = if includes_nul {
+        ret <= *buffer_len
+    } else {
+        ret < *buffer_len
+    };
+    *buffer_len = ret;
+
+    if fits {
+        TRUE
+    } else {
+        unsafe { SetLastError(ERROR_INSUFFICIENT_BUFFER) };
+        FALSE
+    }
+}
 
 /// Get the user name of the current user.
 ///
 /// # Errors
 /// * Returns an error if the username could not be retrieved.
 pub fn get_user_name() -> std::io::Result<OsString> {
-    const BUFFER_LEN: u32 = UNLEN + 1;
+    // # Safety
+    // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+    let buffer = fill_wide_buffer(UNLEN + 1, |buffer, buffer_len| unsafe {
+        GetUserNameW(buffer, buffer_len)
+    })?;
+
+    Ok(OsString::from_wide(&buffer))
+}
+
+// `GetUserNameExW` lives in `secext.h`, which is not exposed by `winapi`.
+#[link(name = "secur32")]
+extern "system" {
+    fn GetUserNameExW(NameFormat: u32, lpNameBuffer: *mut u16, nSize: *mut u32) -> u8;
+}
+
+/// The format of a user name to retrieve with [`get_user_name_ex`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ExtendedNameFormat {
+    /// A SAM-compatible name, like `Engineering\JSmith`.
+    SamCompatible,
+
+    /// A fully qualified distinguished name, like `CN=Jeff Smith,OU=Users,DC=Engineering,DC=Microsoft,DC=Com`.
+    FullyQualifiedDN,
+
+    /// A "friendly" display name, like `Jeff Smith`.
+    Display,
+
+    /// A user principal name, like `someone@example.com`.
+    UserPrincipal,
+
+    /// A DNS domain name followed by a backward-slash and the SAM user name.
+    DnsDomain,
+}
+
+impl ExtendedNameFormat {
+    /// Get the raw `EXTENDED_NAME_FORMAT` value.
+    fn as_raw(self) -> u32 {
+        match self {
+            Self::FullyQualifiedDN => 1,
+            Self::SamCompatible => 2,
+            Self::Display => 3,
+            Self::UserPrincipal => 8,
+            Self::DnsDomain => 12,
+        }
+    }
+}
+
+/// Get the user name of the current user in the given format.
+///
+/// # Errors
+/// * Returns an error if the user name could not be retrieved in the given format.
+pub fn get_user_name_ex(format: ExtendedNameFormat) -> std::io::Result<OsString> {
+    let format = format.as_raw();
+
+    // # Safety
+    // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+    let buffer = fill_wide_buffer(0, |buffer, buffer_len| unsafe {
+        GetUserNameExW(format, buffer, buffer_len).into()
+    })?;
+
+    Ok(OsString::from_wide(&buffer))
+}
+
+/// The maximum length of a NetBIOS computer name, not including the NUL terminator.
+///
+/// This is not exposed by `winapi`.
+const MAX_COMPUTERNAME_LENGTH: u32 = 15;
+
+/// Get the NetBIOS name of the local computer.
+///
+/// # Errors
+/// * Returns an error if the computer name could not be retrieved.
+pub fn get_computer_name() -> std::io::Result<OsString> {
+    const BUFFER_LEN: u32 = MAX_COMPUTERNAME_LENGTH + 1;
 
     let mut buffer_len = BUFFER_LEN;
     let mut buffer = MaybeUninit::<[u16; BUFFER_LEN as usize]>::uninit();
 
     // # Safety
     // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
-    let ret = unsafe { GetUserNameW(buffer.as_mut_ptr().cast(), &mut buffer_len) };
+    let ret = unsafe { GetComputerNameW(buffer.as_mut_ptr().cast(), &mut buffer_len) };
+
+    if ret == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // # Safety
+    // The data must be valid at this point.
+    // The length of data (not including the nul terminator) has been updated and is passed in.
+    // There are only immutable references left to `buffer`, so making another immutable one is safe.
+    let buffer = unsafe { std::slice::from_raw_parts(buffer.as_ptr().cast(), buffer_len as usize) };
+
+    Ok(OsString::from_wide(buffer))
+}
+
+/// The type of computer name to retrieve with [`get_computer_name_ex`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ComputerNameFormat {
+    /// The NetBIOS name of the local computer, or the cluster virtual server if in a cluster.
+    NetBIOS,
+
+    /// The DNS host name of the local computer, or the cluster virtual server if in a cluster.
+    DnsHostname,
+
+    /// The name of the DNS domain of the local computer, or the cluster virtual server if in a cluster.
+    DnsDomain,
+
+    /// The fully qualified DNS name of the local computer, or the cluster virtual server if in a cluster.
+    DnsFullyQualified,
+
+    /// The NetBIOS name of the local computer.
+    PhysicalNetBIOS,
+
+    /// The DNS host name of the local computer.
+    PhysicalDnsHostname,
+
+    /// The name of the DNS domain of the local computer.
+    PhysicalDnsDomain,
+
+    /// The fully qualified DNS name of the local computer.
+    PhysicalDnsFullyQualified,
+}
+
+impl From<ComputerNameFormat> for COMPUTER_NAME_FORMAT {
+    fn from(format: ComputerNameFormat) -> Self {
+        match format {
+            ComputerNameFormat::NetBIOS => ComputerNameNetBIOS,
+            ComputerNameFormat::DnsHostname => ComputerNameDnsHostname,
+            ComputerNameFormat::DnsDomain => ComputerNameDnsDomain,
+            ComputerNameFormat::DnsFullyQualified => ComputerNameDnsFullyQualified,
+            ComputerNameFormat::PhysicalNetBIOS => ComputerNamePhysicalNetBIOS,
+            ComputerNameFormat::PhysicalDnsHostname => ComputerNamePhysicalDnsHostname,
+            ComputerNameFormat::PhysicalDnsDomain => ComputerNamePhysicalDnsDomain,
+            ComputerNameFormat::PhysicalDnsFullyQualified => ComputerNamePhysicalDnsFullyQualified,
+        }
+    }
+}
+
+/// Get a name of the local computer in the given format.
+///
+/// # Errors
+/// * Returns an error if the computer name could not be retrieved.
+pub fn get_computer_name_ex(format: ComputerNameFormat) -> std::io::Result<OsString> {
+    let format = format.into();
+
+    // # Safety
+    // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+    let buffer = fill_wide_buffer(MAX_COMPUTERNAME_LENGTH + 1, |buffer, buffer_len| unsafe {
+        GetComputerNameExW(format, buffer, buffer_len)
+    })?;
+
+    Ok(OsString::from_wide(&buffer))
+}
+
+/// Get a locale name using `GetUserDefaultLocaleName` or `GetSystemDefaultLocaleName`.
+fn get_locale_name(
+    func: unsafe extern "system" fn(*mut u16, c_int) -> c_int,
+) -> std::io::Result<OsString> {
+    let mut buffer = MaybeUninit::<[u16; LOCALE_NAME_MAX_LENGTH]>::uninit();
+
+    // # Safety
+    // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+    let ret = unsafe { func(buffer.as_mut_ptr().cast(), LOCALE_NAME_MAX_LENGTH as c_int) };
 
     if ret == 0 {
         return Err(std::io::Error::last_os_error());
@@ -28,98 +331,794 @@ pub fn get_user_name() -> std::io::Result<OsString> {
 
     // # Safety
     // The data must be valid at this point.
-    // The length of data (minus the nul terminator) has been updated and is passed in.
+    // The length of data (including the nul terminator) has been returned.
     // There are only immutable references left to `buffer`, so making another immutable one is safe.
     let buffer = unsafe {
         // -1 for the NUL terminator.
-        let len = (buffer_len - 1) as usize;
+        let len = (ret - 1) as usize;
         std::slice::from_raw_parts(buffer.as_ptr().cast(), len)
     };
 
     Ok(OsString::from_wide(buffer))
 }
 
+/// Get the locale name of the current user, like `en-US`.
+///
+/// # Errors
+/// * Returns an error if the locale name could not be retrieved.
+pub fn get_user_default_locale_name() -> std::io::Result<OsString> {
+    get_locale_name(GetUserDefaultLocaleName)
+}
+
+/// Get the locale name of the system, like `en-US`.
+///
+/// # Errors
+/// * Returns an error if the locale name could not be retrieved.
+pub fn get_system_default_locale_name() -> std::io::Result<OsString> {
+    get_locale_name(GetSystemDefaultLocaleName)
+}
+
+/// The type of an executable file, as returned by [`get_binary_type`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum BinaryType {
+    /// A 32-bit Windows-based application
+    Scs32BitBinary,
+
+    /// A 64-bit Windows-based application
+    Scs64BitBinary,
+
+    /// An MS-DOS based application
+    ScsDosBinary,
+
+    /// A 16-bit Windows-based application
+    ScsWowBinary,
+
+    /// A PIF file that executes an MS-DOS based application
+    ScsPifBinary,
+
+    /// A POSIX based application
+    ScsPosixBinary,
+
+    /// A 16-bit OS/2-based application
+    ScsOs216Binary,
+
+    /// A binary type this library does not know about
+    Unknown(u32),
+}
+
+impl From<u32> for BinaryType {
+    fn from(binary_type: u32) -> Self {
+        // The `SCS_*` constants are not exposed by `winapi`.
+        match binary_type {
+            0 => Self::Scs32BitBinary,
+            1 => Self::ScsDosBinary,
+            2 => Self::ScsWowBinary,
+            3 => Self::ScsPifBinary,
+            4 => Self::ScsPosixBinary,
+            5 => Self::ScsOs216Binary,
+            6 => Self::Scs64BitBinary,
+            binary_type => Self::Unknown(binary_type),
+        }
+    }
+}
+
+/// Get the type of an executable file.
+///
+/// # Errors
+/// * Returns an error if the file is not an executable or could not be read.
+pub fn get_binary_type(path: &Path) -> std::io::Result<BinaryType> {
+    let path: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut binary_type = 0;
+    let ret = unsafe { GetBinaryTypeW(path.as_ptr(), &mut binary_type) };
+
+    if ret == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(binary_type.into())
+}
+
+/// The type of account a SID refers to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SidNameUse {
+    /// A user
+    User,
+
+    /// A group
+    Group,
+
+    /// A domain
+    Domain,
+
+    /// An alias
+    Alias,
+
+    /// A well-known group
+    WellKnownGroup,
+
+    /// A deleted account
+    DeletedAccount,
+
+    /// An invalid SID
+    Invalid,
+
+    /// An unknown SID type
+    Unknown,
+
+    /// A computer
+    Computer,
+
+    /// A mandatory integrity label
+    Label,
+
+    /// A logon session
+    LogonSession,
+
+    /// A SID type this library does not know about
+    Other(u32),
+}
+
+impl From<SID_NAME_USE> for SidNameUse {
+    // `winapi` exposes the `SID_NAME_USE` variants with their C names.
+    #[allow(non_upper_case_globals)]
+    fn from(sid_name_use: SID_NAME_USE) -> Self {
+        match sid_name_use {
+            SidTypeUser => Self::User,
+            SidTypeGroup => Self::Group,
+            SidTypeDomain => Self::Domain,
+            SidTypeAlias => Self::Alias,
+            SidTypeWellKnownGroup => Self::WellKnownGroup,
+            SidTypeDeletedAccount => Self::DeletedAccount,
+            SidTypeInvalid => Self::Invalid,
+            SidTypeUnknown => Self::Unknown,
+            SidTypeComputer => Self::Computer,
+            SidTypeLabel => Self::Label,
+            SidTypeLogonSession => Self::LogonSession,
+            sid_name_use => Self::Other(sid_name_use),
+        }
+    }
+}
+
+/// Info about an account, as returned by [`lookup_account_name`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SidInfo {
+    /// The SID of the account
+    pub sid: Vec<u8>,
+
+    /// The name of the domain the account was found in
+    pub domain: OsString,
+
+    /// The type of the account
+    pub use_: SidNameUse,
+}
+
+/// Look up the SID of an account on the local system.
+///
+/// # Errors
+/// * Returns an error if the account could not be found.
+pub fn lookup_account_name(name: &OsStr) -> std::io::Result<SidInfo> {
+    let name: Vec<u16> = name.encode_wide().chain(std::iter::once(0)).collect();
+
+    let mut sid_len = 0;
+    let mut domain_len = 0;
+    let mut use_ = 0;
+
+    // Probe for the required buffer sizes.
+    let ret = unsafe {
+        LookupAccountNameW(
+            std::ptr::null(),
+            name.as_ptr(),
+            std::ptr::null_mut(),
+            &mut sid_len,
+            std::ptr::null_mut(),
+            &mut domain_len,
+            &mut use_,
+        )
+    };
+
+    if ret == 0 && unsafe { GetLastError() } != ERROR_INSUFFICIENT_BUFFER {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut sid: Vec<u8> = vec![0; sid_len as usize];
+    // `domain_len` includes the nul terminator.
+    let mut domain: Vec<u16> = vec![0; domain_len as usize];
+
+    let ret = unsafe {
+        LookupAccountNameW(
+            std::ptr::null(),
+            name.as_ptr(),
+            sid.as_mut_ptr().cast(),
+            &mut sid_len,
+            domain.as_mut_ptr(),
+            &mut domain_len,
+            &mut use_,
+        )
+    };
+
+    if ret == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // `domain_len` has been updated to not include the nul terminator.
+    sid.truncate(sid_len as usize);
+    domain.truncate(domain_len as usize);
+
+    Ok(SidInfo {
+        sid,
+        domain: OsString::from_wide(&domain),
+        use_: use_.into(),
+    })
+}
+
+/// Check the OS version against a version with `VerifyVersionInfoW`.
+///
+/// The build number is only compared if `build` is `Some`.
+/// The major and minor versions are compared hierarchically, while the build number is compared on its own.
+fn verify_version(major: u32, minor: u32, build: Option<u32>, condition: u8) -> bool {
+    let mut info: OSVERSIONINFOEXW = unsafe { std::mem::zeroed() };
+    info.dwOSVersionInfoSize = std::mem::size_of::<OSVERSIONINFOEXW>() as u32;
+    info.dwMajorVersion = major;
+    info.dwMinorVersion = minor;
+
+    let mut type_mask = VER_MAJORVERSION | VER_MINORVERSION;
+    let mut condition_mask = unsafe {
+        let mask = VerSetConditionMask(0, VER_MAJORVERSION, condition);
+        VerSetConditionMask(mask, VER_MINORVERSION, condition)
+    };
+
+    if let Some(build) = build {
+        info.dwBuildNumber = build;
+        type_mask |= VER_BUILDNUMBER;
+        condition_mask = unsafe { VerSetConditionMask(condition_mask, VER_BUILDNUMBER, condition) };
+    }
+
+    // This fails with `ERROR_OLD_WIN_VERSION` if the condition is not met.
+    unsafe { VerifyVersionInfoW(&mut info, type_mask, condition_mask) != 0 }
+}
+
+/// Check if the OS version is at least the given version.
+///
+/// Like `GetVersionEx`, this depends on the compatibility manifest of the calling process.
+/// On Windows 8.1 and later, a process without a manifest declaring support for a newer version
+/// is treated as running on Windows 8 (`6.2`), so checking for Windows 10 returns `false`.
+///
+/// For example, Windows 7 is `6.1.7600` and Windows 10 is `10.0.10240`.
+pub fn is_os_version_at_least(major: u32, minor: u32, build: u32) -> bool {
+    // `VerifyVersionInfoW` compares the build number separately from the major and minor versions,
+    // so the build number only matters if the major and minor versions are equal.
+    verify_version(major, minor, None, VER_GREATER)
+        || verify_version(major, minor, Some(build), VER_GREATER_EQUAL)
+}
+
+/// Get the session ID of the session attached to the physical console.
+///
+/// Returns `None` if no session is attached, like while a session is connecting or disconnecting.
+pub fn get_active_console_session_id() -> Option<u32> {
+    let session_id = unsafe { WTSGetActiveConsoleSessionId() };
+
+    if session_id == 0xFFFF_FFFF {
+        None
+    } else {
+        Some(session_id)
+    }
+}
+
+/// Get the current directory of this process.
+///
+/// # Errors
+/// * Returns an error if the current directory could not be retrieved.
+pub fn get_current_directory() -> std::io::Result<PathBuf> {
+    // The current directory may change between calls, so this retries until the buffer is large enough.
+    let buffer = fill_wide_buffer(0, |buffer, buffer_len| {
+        // # Safety
+        // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+        let ret = unsafe { GetCurrentDirectoryW(*buffer_len, buffer) };
+        wide_len_result(ret, buffer_len, false)
+    })?;
+
+    Ok(OsString::from_wide(&buffer).into())
+}
+
+/// Set the current directory of this process.
+///
+/// # Errors
+/// * Returns an error if the current directory could not be set.
+pub fn set_current_directory(path: &Path) -> std::io::Result<()> {
+    let path: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let ret = unsafe { SetCurrentDirectoryW(path.as_ptr()) };
+
+    if ret == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Get the value of an environment variable of this process.
+///
+/// Returns `None` if the variable does not exist.
+///
+/// # Errors
+/// * Returns an error if the environment variable could not be retrieved.
+pub fn get_environment_variable(name: &OsStr) -> std::io::Result<Option<OsString>> {
+    let name: Vec<u16> = name.encode_wide().chain(std::iter::once(0)).collect();
+
+    // The variable may change between calls, so this retries until the buffer is large enough.
+    let ret = fill_wide_buffer(0, |buffer, buffer_len| {
+        // An empty variable returns 0 without setting the last error.
+        unsafe { SetLastError(ERROR_SUCCESS) };
+
+        // # Safety
+        // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+        let ret = unsafe { GetEnvironmentVariableW(name.as_ptr(), buffer, *buffer_len) };
+
+        if ret == 0 && unsafe { GetLastError() } == ERROR_SUCCESS {
+            *buffer_len = 0;
+            return TRUE;
+        }
+
+        wide_len_result(ret, buffer_len, false)
+    });
+
+    match ret {
+        Ok(buffer) => Ok(Some(OsString::from_wide(&buffer))),
+        Err(e) if e.raw_os_error() == Some(ERROR_ENVVAR_NOT_FOUND as i32) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Set the value of an environment variable of this process.
+///
+/// If `value` is `None`, the variable is deleted.
+///
+/// # Errors
+/// * Returns an error if the environment variable could not be set.
+pub fn set_environment_variable(name: &OsStr, value: Option<&OsStr>) -> std::io::Result<()> {
+    let name: Vec<u16> = name.encode_wide().chain(std::iter::once(0)).collect();
+    let value: Option<Vec<u16>> =
+        value.map(|value| value.encode_wide().chain(std::iter::once(0)).collect());
+
+    let ret = unsafe {
+        SetEnvironmentVariableW(
+            name.as_ptr(),
+            value
+                .as_ref()
+                .map_or(std::ptr::null(), |value| value.as_ptr()),
+        )
+    };
+
+    if ret == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Expand the environment variable references in a string, like `%USERPROFILE%\Documents`.
+///
+/// References to undefined variables are left unchanged.
+///
+/// # Errors
+/// * Returns an error if the string could not be expanded.
+pub fn expand_environment_strings(input: &OsStr) -> std::io::Result<OsString> {
+    let input: Vec<u16> = input.encode_wide().chain(std::iter::once(0)).collect();
+
+    // The environment may change between calls, so this retries until the buffer is large enough.
+    let buffer = fill_wide_buffer(0, |buffer, buffer_len| {
+        // # Safety
+        // This is safe as the buffer exists and the correct buffer length is passed to this function for initialization.
+        let ret = unsafe { ExpandEnvironmentStringsW(input.as_ptr(), buffer, *buffer_len) };
+        wide_len_result(ret, buffer_len, true)
+    })?;
+
+    Ok(OsString::from_wide(&buffer))
+}
+
+/// Expand the environment variable references in a string, like [`expand_environment_strings`],
+/// while also returning the names of the variables that could not be expanded.
+///
+/// A variable is reported if its `%NAME%` reference is in the input and is left unchanged in the output.
+/// This may also report references that were produced by the expansion of another variable.
+///
+/// # Errors
+/// * Returns an error if the string could not be expanded.
+pub fn expand_environment_strings_checked(
+    input: &OsStr,
+) -> std::io::Result<(OsString, Vec<OsString>)> {
+    let output = expand_environment_strings(input)?;
+
+    let input: Vec<u16> = input.encode_wide().collect();
+    let output_wide: Vec<u16> = output.encode_wide().collect();
+
+    let mut unexpanded: Vec<OsString> = Vec::new();
+    for reference in env_var_references(&input) {
+        let name = OsString::from_wide(&reference[1..reference.len() - 1]);
+        let is_unexpanded = output_wide
+            .windows(reference.len())
+            .any(|window| window == reference);
+
+        if is_unexpanded && !unexpanded.contains(&name) {
+            unexpanded.push(name);
+        }
+    }
+
+    Ok((output, unexpanded))
+}
+
+/// Find the `%NAME%` references in a wide string, including the surrounding `%`s.
+fn env_var_references(s: &[u16]) -> Vec<&[u16]> {
+    const PERCENT: u16 = b'%' as u16;
+
+    let mut references = Vec::new();
+    let mut start = None;
+    for (i, c) in s.iter().copied().enumerate() {
+        if c != PERCENT {
+            continue;
+        }
+
+        match start {
+            // `%%` is not a reference, so the second `%` may start one.
+            Some(start_i) if i - start_i > 1 => {
+                references.push(&s[start_i..=i]);
+                start = None;
+            }
+            _ => start = Some(i),
+        }
+    }
+
+    references
+}
+
+/// Send a string to the debugger with `OutputDebugStringW`.
+///
+/// If no debugger is attached, this does nothing.
+pub fn output_debug_string(msg: &OsStr) {
+    let msg: Vec<u16> = msg.encode_wide().chain(std::iter::once(0)).collect();
+    unsafe { OutputDebugStringW(msg.as_ptr()) }
+}
+
+/// Send a [`str`] to the debugger with `OutputDebugStringW`.
+///
+/// See [`output_debug_string`].
+pub fn output_debug_str(msg: &str) {
+    output_debug_string(msg.as_ref())
+}
+
 /// A Wide String that has been allocated with `LocalAlloc`.
-#[repr(transparent)]
-pub struct LocalWideString(NonNull<u16>);
+///
+/// The length is computed once on construction and cached.
+pub struct LocalWideString {
+    ptr: NonNull<u16>,
+    len: usize,
+}
 
 impl LocalWideString {
     /// Make a [`LocalWideString`] from a ptr.
     ///
+    /// This is an O(n) operation, as the length of the string is computed.
+    ///
+    /// # Safety
+    /// ptr must be a valid LPWSTR allocated with `LocalAlloc`.
+    ///
+    /// # Panics
+    /// Panics if the length cannot fit in a `usize`.
+    pub unsafe fn from_raw(ptr: NonNull<u16>) -> Self {
+        let len = lstrlenW(ptr.as_ptr())
+            .try_into()
+            .expect("len cannot fit in a `usize`");
+
+        Self::from_raw_with_len(ptr, len)
+    }
+
+    /// Make a [`LocalWideString`] from a ptr and a known length in characters, not including the NUL terminator.
+    ///
+    /// This is useful for APIs like `FormatMessageW` that return the length of the string they allocate.
+    ///
+    /// # Safety
+    /// * ptr must be a valid LPWSTR allocated with `LocalAlloc`.
+    /// * `len` must be the number of characters in the string before the NUL terminator.
+    pub unsafe fn from_raw_with_len(ptr: NonNull<u16>, len: usize) -> Self {
+        Self { ptr, len }
+    }
+
+    /// Get a mut ptr to the string
+    pub fn as_mut_ptr(&mut self) -> *mut u16 {
+        self.ptr.as_ptr()
+    }
+
+    /// Get the length of the string in characters.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    //// Check if this string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get this string as a slice of u16s.
+    pub fn as_slice(&self) -> &[u16] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len()) }
+    }
+
+    /// Get this as an [`OsString`].
+    pub fn as_os_string(&self) -> OsString {
+        OsString::from_wide(self.as_slice())
+    }
+
+    /// Convert this into an [`OsString`], freeing the underlying buffer.
+    pub fn into_os_string(self) -> OsString {
+        self.as_os_string()
+    }
+
+    /// Convert this into a [`Vec`] of u16s, freeing the underlying buffer.
+    ///
+    /// This does not include the NUL terminator.
+    pub fn into_wide_vec(self) -> Vec<u16> {
+        self.as_slice().to_vec()
+    }
+
+    /// Convert this to a [`String`].
+    ///
+    /// # Errors
+    /// Returns an error if this contains invalid utf16
+    pub fn to_str(&self) -> Result<String, std::string::FromUtf16Error> {
+        String::from_utf16(self.as_slice())
+    }
+
+    /// Convert this to a [`String`] lossily.
+    pub fn to_str_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_slice())
+    }
+
+    /// Try to iterate over the chars in this string
+    pub fn chars(&self) -> impl Iterator<Item = Result<char, std::char::DecodeUtf16Error>> + '_ {
+        std::char::decode_utf16(self.as_slice().iter().copied())
+    }
+
+    /// Get a struct that implements display lossily for this string.
+    pub fn display(&self) -> LocalWideStringDisplay {
+        LocalWideStringDisplay(self)
+    }
+
+    /// Try to destroy this object.
+    ///
+    /// # Errors
+    /// Returns a tuple of this object and an error if this object could not be destroyed.
+    pub fn destroy(self) -> Result<(), (Self, std::io::Error)> {
+        let mut obj = ManuallyDrop::new(self);
+        let ret = unsafe { LocalFree(obj.as_mut_ptr().cast()) };
+
+        if ret.is_null() {
+            Ok(())
+        } else {
+            Err((
+                ManuallyDrop::into_inner(obj),
+                std::io::Error::last_os_error(),
+            ))
+        }
+    }
+}
+
+impl Drop for LocalWideString {
+    fn drop(&mut self) {
+        std::mem::forget(
+            Self {
+                ptr: self.ptr,
+                len: self.len,
+            }
+            .destroy(),
+        );
+    }
+}
+
+impl std::fmt::Debug for LocalWideString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_char('"')?;
+        for c in self
+            .chars()
+            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        {
+            for c in c.escape_debug() {
+                f.write_char(c)?
+            }
+        }
+
+        f.write_char('"')?;
+
+        Ok(())
+    }
+}
+
+/// A struct that implements display for [`LocalWideString`]
+pub struct LocalWideStringDisplay<'a>(&'a LocalWideString);
+
+impl std::fmt::Display for LocalWideStringDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for c in self
+            .0
+            .chars()
+            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        {
+            f.write_char(c)?
+        }
+
+        Ok(())
+    }
+}
+
+/// A buffer of bytes that has been allocated with `LocalAlloc`.
+pub struct LocalBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+impl LocalBuffer {
+    /// Allocate a new zeroed [`LocalBuffer`] of `len` bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the memory could not be allocated.
+    pub fn alloc(len: usize) -> std::io::Result<Self> {
+        let ptr = unsafe { LocalAlloc(LPTR, len) };
+
+        match NonNull::new(ptr.cast()) {
+            Some(ptr) => Ok(unsafe { Self::from_raw_parts(ptr, len) }),
+            None => Err(std::io::Error::last_os_error()),
+        }
+    }
+
+    /// Make a [`LocalBuffer`] from a ptr and a length in bytes.
+    ///
     /// # Safety
-    /// ptr must be a valid LPWSTR allocated with `LocalAlloc`.
-    pub unsafe fn from_raw(ptr: NonNull<u16>) -> Self {
-        Self(ptr)
+    /// * ptr must be a valid pointer allocated with `LocalAlloc`.
+    /// * `len` bytes must be initialized and within the allocation.
+    pub unsafe fn from_raw_parts(ptr: NonNull<u8>, len: usize) -> Self {
+        Self { ptr, len }
     }
 
-    /// Get a mut ptr to the string
-    pub fn as_mut_ptr(&mut self) -> *mut u16 {
-        self.0.as_ptr()
+    /// Get a ptr to the buffer.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
     }
 
-    /// Get the length of the string in characters.
-    ///
-    /// This is an O(n) operation.
-    ///
-    /// # Panics
-    /// Panics if the length cannot fit in a `usize`.
+    /// Get a mut ptr to the buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Get the length of the buffer in bytes.
     pub fn len(&self) -> usize {
-        unsafe {
-            lstrlenW(self.0.as_ptr())
-                .try_into()
-                .expect("len cannot fit in a `usize`")
-        }
+        self.len
     }
 
-    //// Check if this string is empty.
-    ///
-    /// This is an O(n) operation.
-    ///
-    /// # Panics
-    /// Panics if the length cannot fit in a `usize`.
+    /// Check if this buffer is empty.
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    /// Get this string as a slice of u16s.
-    ///
-    /// This is an O(n) operation.
-    ///
-    /// # Panics
-    /// Panics if the length cannot fit in a `usize`.
-    pub fn as_slice(&self) -> &[u16] {
-        unsafe { std::slice::from_raw_parts(self.0.as_ptr(), self.len()) }
+    /// Get this buffer as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
     }
 
-    /// Get this as an [`OsString`].
+    /// Get this buffer as a mut slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Try to destroy this object.
     ///
-    /// # Panics
-    /// Panics if the length cannot fit in a `usize`.
-    pub fn as_os_string(&self) -> OsString {
-        OsString::from_wide(self.as_slice())
+    /// # Errors
+    /// Returns a tuple of this object and an error if this object could not be destroyed.
+    pub fn destroy(self) -> Result<(), (Self, std::io::Error)> {
+        let mut obj = ManuallyDrop::new(self);
+        let ret = unsafe { LocalFree(obj.as_mut_ptr().cast()) };
+
+        if ret.is_null() {
+            Ok(())
+        } else {
+            Err((
+                ManuallyDrop::into_inner(obj),
+                std::io::Error::last_os_error(),
+            ))
+        }
     }
+}
 
-    /// Convert this to a [`String`].
+impl Drop for LocalBuffer {
+    fn drop(&mut self) {
+        std::mem::forget(
+            Self {
+                ptr: self.ptr,
+                len: self.len,
+            }
+            .destroy(),
+        );
+    }
+}
+
+impl std::fmt::Debug for LocalBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalBuffer")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// A buffer of bytes that has been allocated from the process heap with `HeapAlloc`.
+pub struct ProcessHeapBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+impl ProcessHeapBuffer {
+    /// Allocate a new zeroed [`ProcessHeapBuffer`] of `len` bytes.
     ///
     /// # Errors
-    /// Returns an error if this contains invalid utf16
-    pub fn to_str(&self) -> Result<String, std::string::FromUtf16Error> {
-        String::from_utf16(self.as_slice())
+    /// Returns an error if the memory could not be allocated.
+    pub fn alloc(len: usize) -> std::io::Result<Self> {
+        let heap = unsafe { GetProcessHeap() };
+        if heap.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // `HeapAlloc` does not set the last error on failure.
+        let ptr = unsafe { HeapAlloc(heap, HEAP_ZERO_MEMORY, len) };
+        let ptr = NonNull::new(ptr.cast())
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::OutOfMemory))?;
+
+        Ok(unsafe { Self::from_raw_parts(ptr, len) })
     }
 
-    /// Convert this to a [`String`] lossily.
-    pub fn to_str_lossy(&self) -> String {
-        String::from_utf16_lossy(self.as_slice())
+    /// Make a [`ProcessHeapBuffer`] from a ptr and a length in bytes.
+    ///
+    /// # Safety
+    /// * ptr must be a valid pointer allocated from the process heap with `HeapAlloc`.
+    /// * `len` bytes must be initialized and within the allocation.
+    pub unsafe fn from_raw_parts(ptr: NonNull<u8>, len: usize) -> Self {
+        Self { ptr, len }
     }
 
-    /// Try to iterate over the chars in this string
-    pub fn chars(&self) -> impl Iterator<Item = Result<char, std::char::DecodeUtf16Error>> + '_ {
-        std::char::decode_utf16(self.as_slice().iter().copied())
+    /// Get a ptr to the buffer.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
     }
 
-    /// Get a struct that implements display lossily for this string.
-    pub fn display(&self) -> LocalWideStringDisplay {
-        LocalWideStringDisplay(self)
+    /// Get a mut ptr to the buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Get the length of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if this buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get this buffer as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Get this buffer as a mut slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
     }
 
     /// Try to destroy this object.
@@ -128,9 +1127,9 @@ impl LocalWideString {
     /// Returns a tuple of this object and an error if this object could not be destroyed.
     pub fn destroy(self) -> Result<(), (Self, std::io::Error)> {
         let mut obj = ManuallyDrop::new(self);
-        let ret = unsafe { LocalFree(obj.as_mut_ptr().cast()) };
+        let ret = unsafe { HeapFree(GetProcessHeap(), 0, obj.as_mut_ptr().cast()) };
 
-        if ret.is_null() {
+        if ret != 0 {
             Ok(())
         } else {
             Err((
@@ -141,45 +1140,149 @@ impl LocalWideString {
     }
 }
 
-impl Drop for LocalWideString {
+impl Drop for ProcessHeapBuffer {
     fn drop(&mut self) {
-        std::mem::forget(Self(self.0).destroy());
+        std::mem::forget(
+            Self {
+                ptr: self.ptr,
+                len: self.len,
+            }
+            .destroy(),
+        );
     }
 }
 
-impl std::fmt::Debug for LocalWideString {
+impl std::fmt::Debug for ProcessHeapBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_char('"')?;
-        for c in self
-            .chars()
-            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
-        {
-            for c in c.escape_debug() {
-                f.write_char(c)?
-            }
+        f.debug_struct("ProcessHeapBuffer")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// The number of [`FileTime`] ticks in a second.
+const FILE_TIME_TICKS_PER_SEC: u64 = 10_000_000;
+
+/// The number of seconds between January 1, 1601 (UTC) and the unix epoch, January 1, 1970 (UTC).
+const UNIX_EPOCH_SECS_SINCE_1601: u64 = 11_644_473_600;
+
+/// A `FILETIME`, the number of 100-nanosecond intervals since January 1, 1601 (UTC).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct FileTime(u64);
+
+impl FileTime {
+    /// Get the current system time.
+    pub fn now() -> Self {
+        let mut file_time = MaybeUninit::<FILETIME>::uninit();
+
+        // # Safety
+        // `GetSystemTimeAsFileTime` cannot fail and always initializes the output.
+        let file_time = unsafe {
+            GetSystemTimeAsFileTime(file_time.as_mut_ptr());
+            file_time.assume_init()
+        };
+
+        file_time.into()
+    }
+
+    /// Make a [`FileTime`] from a number of 100-nanosecond intervals since January 1, 1601 (UTC).
+    pub fn from_ticks(ticks: u64) -> Self {
+        Self(ticks)
+    }
+
+    /// Get the number of 100-nanosecond intervals since January 1, 1601 (UTC).
+    pub fn ticks(self) -> u64 {
+        self.0
+    }
+
+    /// Get the time since January 1, 1601 (UTC) as a [`Duration`].
+    pub fn as_duration_since_1601(self) -> Duration {
+        let secs = self.0 / FILE_TIME_TICKS_PER_SEC;
+        // `as` is fine here, as the remainder is less than `FILE_TIME_TICKS_PER_SEC`.
+        let nanos = (self.0 % FILE_TIME_TICKS_PER_SEC) as u32 * 100;
+        Duration::new(secs, nanos)
+    }
+
+    /// Convert this into a [`SystemTime`].
+    ///
+    /// Returns `None` if this time cannot be represented as a [`SystemTime`] on this platform.
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        let since_1601 = self.as_duration_since_1601();
+        let unix_epoch_since_1601 = Duration::from_secs(UNIX_EPOCH_SECS_SINCE_1601);
+
+        match since_1601.checked_sub(unix_epoch_since_1601) {
+            Some(since_unix_epoch) => UNIX_EPOCH.checked_add(since_unix_epoch),
+            None => UNIX_EPOCH.checked_sub(unix_epoch_since_1601 - since_1601),
         }
+    }
 
-        f.write_char('"')?;
+    /// Make a [`FileTime`] from a [`SystemTime`].
+    ///
+    /// Sub-100-nanosecond precision is truncated.
+    /// Returns `None` if the time is before January 1, 1601 (UTC), or too far in the future to fit in a [`FileTime`].
+    pub fn from_system_time(system_time: SystemTime) -> Option<Self> {
+        let unix_epoch_since_1601 = Duration::from_secs(UNIX_EPOCH_SECS_SINCE_1601);
+        let since_1601 = match system_time.duration_since(UNIX_EPOCH) {
+            Ok(since_unix_epoch) => unix_epoch_since_1601.checked_add(since_unix_epoch)?,
+            Err(e) => unix_epoch_since_1601.checked_sub(e.duration())?,
+        };
 
-        Ok(())
+        let ticks = since_1601
+            .as_secs()
+            .checked_mul(FILE_TIME_TICKS_PER_SEC)?
+            .checked_add(u64::from(since_1601.subsec_nanos() / 100))?;
+
+        Some(Self(ticks))
     }
 }
 
-/// A struct that implements display for [`LocalWideString`]
-pub struct LocalWideStringDisplay<'a>(&'a LocalWideString);
+impl From<FILETIME> for FileTime {
+    fn from(file_time: FILETIME) -> Self {
+        Self((u64::from(file_time.dwHighDateTime) << 32) | u64::from(file_time.dwLowDateTime))
+    }
+}
 
-impl std::fmt::Display for LocalWideStringDisplay<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for c in self
-            .0
-            .chars()
-            .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
-        {
-            f.write_char(c)?
+impl From<FileTime> for FILETIME {
+    fn from(file_time: FileTime) -> Self {
+        FILETIME {
+            dwLowDateTime: file_time.0 as u32,
+            dwHighDateTime: (file_time.0 >> 32) as u32,
         }
+    }
+}
 
-        Ok(())
+/// Convert a `SYSTEMTIME` into a [`FileTime`].
+///
+/// # Errors
+/// Returns an error if the `SYSTEMTIME` is invalid.
+pub fn system_time_to_file_time(system_time: &SYSTEMTIME) -> std::io::Result<FileTime> {
+    let mut file_time = MaybeUninit::<FILETIME>::uninit();
+    let ret = unsafe { SystemTimeToFileTime(system_time, file_time.as_mut_ptr()) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(unsafe { file_time.assume_init() }.into())
+}
+
+/// Convert a [`FileTime`] into a `SYSTEMTIME`.
+///
+/// `SYSTEMTIME` only has millisecond precision, so sub-millisecond ticks are truncated.
+///
+/// # Errors
+/// Returns an error if the [`FileTime`] is too large to be represented as a `SYSTEMTIME`.
+pub fn file_time_to_system_time(file_time: &FileTime) -> std::io::Result<SYSTEMTIME> {
+    let file_time: FILETIME = (*file_time).into();
+    let mut system_time = MaybeUninit::<SYSTEMTIME>::uninit();
+    let ret = unsafe { FileTimeToSystemTime(&file_time, system_time.as_mut_ptr()) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
     }
+
+    Ok(unsafe { system_time.assume_init() })
 }
 
 #[cfg(test)]
@@ -191,4 +1294,322 @@ mod test {
         let user_name = get_user_name().unwrap();
         dbg!(user_name);
     }
+
+    #[test]
+    fn fill_wide_buffer_grows() {
+        let user_name = get_user_name().expect("failed to get user name");
+
+        let mut calls = 0;
+        let buffer = fill_wide_buffer(1, |buffer, buffer_len| {
+            calls += 1;
+            unsafe { GetUserNameW(buffer, buffer_len) }
+        })
+        .expect("failed to get user name");
+        assert_eq!(OsString::from_wide(&buffer), user_name);
+        assert!(calls > 1);
+    }
+
+    #[test]
+    fn fill_wide_buffer_unreported_len() {
+        let data: Vec<u16> = "Hello World!".encode_utf16().collect();
+
+        // This fails without reporting the required length, so the buffer is doubled until it fits.
+        let mut calls = 0;
+        let buffer = fill_wide_buffer(0, |buffer, buffer_len| {
+            calls += 1;
+            if (*buffer_len as usize) < data.len() {
+                unsafe { SetLastError(ERROR_MORE_DATA) };
+                return FALSE;
+            }
+
+            unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len()) };
+            *buffer_len = data.len() as u32;
+            TRUE
+        })
+        .expect("failed to fill buffer");
+        assert_eq!(buffer, data);
+        assert!(calls > 1);
+    }
+
+    #[test]
+    fn file_time_system_time_round_trip() {
+        // 1 millisecond, the precision of a `SYSTEMTIME`.
+        const TICKS_PER_MILLI: u64 = 10_000;
+
+        let now = FileTime::now();
+        let system_time = file_time_to_system_time(&now).expect("failed to convert to SYSTEMTIME");
+        let round_trip =
+            system_time_to_file_time(&system_time).expect("failed to convert to FILETIME");
+
+        assert!(round_trip <= now);
+        assert!(now.ticks() - round_trip.ticks() < TICKS_PER_MILLI);
+    }
+
+    #[test]
+    fn file_time_to_system_time_known() {
+        // 2000-01-01T00:00:00Z, 946684800 seconds after the unix epoch.
+        let file_time = FileTime::from_ticks(125_911_584_000_000_000);
+        assert_eq!(
+            file_time.to_system_time(),
+            Some(UNIX_EPOCH + Duration::from_secs(946_684_800))
+        );
+        assert_eq!(
+            FileTime::from_system_time(UNIX_EPOCH + Duration::from_secs(946_684_800)),
+            Some(file_time)
+        );
+
+        // The unix epoch itself.
+        let file_time = FileTime::from_ticks(116_444_736_000_000_000);
+        assert_eq!(file_time.to_system_time(), Some(UNIX_EPOCH));
+        assert_eq!(FileTime::from_system_time(UNIX_EPOCH), Some(file_time));
+
+        // Sub-second ticks.
+        let file_time = FileTime::from_ticks(116_444_736_000_000_001);
+        assert_eq!(
+            file_time.to_system_time(),
+            Some(UNIX_EPOCH + Duration::from_nanos(100))
+        );
+        assert_eq!(
+            file_time.as_duration_since_1601(),
+            Duration::new(11_644_473_600, 100)
+        );
+    }
+
+    #[test]
+    fn file_time_system_time_edge_cases() {
+        // The earliest `FILETIME` is before the unix epoch.
+        let file_time = FileTime::from_ticks(0);
+        assert_eq!(file_time.as_duration_since_1601(), Duration::ZERO);
+        let system_time = file_time.to_system_time().expect("failed to convert");
+        assert_eq!(FileTime::from_system_time(system_time), Some(file_time));
+
+        // Times before 1601 cannot be represented.
+        assert_eq!(
+            FileTime::from_system_time(system_time - Duration::from_secs(1)),
+            None
+        );
+
+        let now = FileTime::now();
+        let round_trip =
+            FileTime::from_system_time(now.to_system_time().expect("failed to convert"));
+        assert_eq!(round_trip, Some(now));
+    }
+
+    #[test]
+    fn get_computer_name_works() {
+        let computer_name = get_computer_name().unwrap();
+        assert!(!computer_name.is_empty());
+        dbg!(computer_name);
+    }
+
+    #[test]
+    fn lookup_account_name_works() {
+        let user_name = get_user_name().unwrap();
+        let info = lookup_account_name(&user_name).expect("failed to look up account");
+        assert!(!info.sid.is_empty());
+        assert!(!info.domain.is_empty());
+        assert_eq!(info.use_, SidNameUse::User);
+    }
+
+    #[test]
+    fn is_os_version_at_least_works() {
+        // Windows 7
+        assert!(is_os_version_at_least(6, 1, 0));
+        assert!(!is_os_version_at_least(u32::MAX, 0, 0));
+    }
+
+    #[test]
+    fn get_user_name_ex_works() {
+        let user_name = get_user_name_ex(ExtendedNameFormat::SamCompatible).unwrap();
+        assert!(!user_name.is_empty());
+        dbg!(user_name);
+    }
+
+    #[test]
+    fn get_computer_name_ex_works() {
+        let computer_name = get_computer_name_ex(ComputerNameFormat::NetBIOS).unwrap();
+        assert!(!computer_name.is_empty());
+        dbg!(computer_name);
+    }
+
+    /// Allocate a [`LocalWideString`] with `LocalAlloc`.
+    fn local_wide_string(data: &str) -> LocalWideString {
+        let data: Vec<u16> = data.encode_utf16().chain(std::iter::once(0)).collect();
+        let ptr: *mut u16 = unsafe { LocalAlloc(0, data.len() * 2).cast() };
+        let ptr = NonNull::new(ptr).expect("failed to allocate memory");
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr.as_ptr(), data.len());
+            LocalWideString::from_raw_with_len(ptr, data.len() - 1)
+        }
+    }
+
+    #[test]
+    fn local_wide_string_cached_len() {
+        let mut s = local_wide_string("hello");
+        assert_eq!(s.as_slice().len(), 5);
+
+        // The length is cached, so shortening the string in place is not observed.
+        unsafe {
+            s.as_mut_ptr().write(0);
+        }
+        assert_eq!(s.as_slice().len(), 5);
+    }
+
+    #[test]
+    fn local_wide_string_into_os_string() {
+        let s = local_wide_string("hello");
+        assert_eq!(s.into_os_string(), "hello");
+    }
+
+    #[test]
+    fn local_wide_string_into_wide_vec() {
+        let s = local_wide_string("hello");
+        let expected: Vec<u16> = "hello".encode_utf16().collect();
+        assert_eq!(s.into_wide_vec(), expected);
+    }
+
+    #[test]
+    fn local_buffer_read_write() {
+        let mut buffer = LocalBuffer::alloc(16).expect("failed to allocate buffer");
+        assert_eq!(buffer.len(), 16);
+        assert!(buffer.as_slice().iter().all(|&b| b == 0));
+
+        buffer.as_mut_slice().copy_from_slice(b"Hello World!!!!!");
+        assert_eq!(buffer.as_slice(), b"Hello World!!!!!");
+        buffer.destroy().expect("failed to destroy buffer");
+    }
+
+    #[test]
+    fn process_heap_buffer_read_write() {
+        let mut buffer = ProcessHeapBuffer::alloc(32).expect("failed to allocate buffer");
+        assert_eq!(buffer.len(), 32);
+        assert!(buffer.as_slice().iter().all(|&b| b == 0));
+
+        for (i, b) in buffer.as_mut_slice().iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        assert!(buffer
+            .as_slice()
+            .iter()
+            .enumerate()
+            .all(|(i, &b)| b == i as u8));
+        drop(buffer);
+    }
+
+    #[test]
+    fn get_user_default_locale_name_works() {
+        let locale_name = get_user_default_locale_name().unwrap();
+        let locale_name = locale_name
+            .to_str()
+            .expect("locale name is not valid unicode");
+        assert!(!locale_name.is_empty());
+        assert!(locale_name.contains('-'));
+    }
+
+    #[test]
+    fn get_system_default_locale_name_works() {
+        let locale_name = get_system_default_locale_name().unwrap();
+        assert!(!locale_name.is_empty());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn get_binary_type_works() {
+        let system_root = std::env::var_os("SystemRoot").expect("missing `SystemRoot`");
+        let notepad = Path::new(&system_root).join("System32").join("notepad.exe");
+        let binary_type = get_binary_type(&notepad).expect("failed to get binary type");
+        assert_eq!(binary_type, BinaryType::Scs64BitBinary);
+    }
+
+    #[test]
+    fn get_active_console_session_id_works() {
+        let session_id = get_active_console_session_id();
+        dbg!(session_id);
+    }
+
+    #[test]
+    fn current_directory_round_trip() {
+        let original = get_current_directory().expect("failed to get current directory");
+        assert_eq!(original, std::env::current_dir().unwrap());
+
+        let temp_dir = std::env::temp_dir();
+        set_current_directory(&temp_dir).expect("failed to set current directory");
+        let current = get_current_directory().expect("failed to get current directory");
+
+        set_current_directory(&original).expect("failed to restore current directory");
+
+        assert_eq!(
+            std::fs::canonicalize(current).unwrap(),
+            std::fs::canonicalize(temp_dir).unwrap()
+        );
+    }
+
+    #[test]
+    fn environment_variable_round_trip() {
+        let name = OsStr::new("SKYLIGHT_ENVIRONMENT_VARIABLE_ROUND_TRIP");
+
+        set_environment_variable(name, Some(OsStr::new("Hello World!")))
+            .expect("failed to set environment variable");
+        let value = get_environment_variable(name).expect("failed to get environment variable");
+        assert_eq!(value.as_deref(), Some(OsStr::new("Hello World!")));
+
+        set_environment_variable(name, Some(OsStr::new("")))
+            .expect("failed to set environment variable");
+        let value = get_environment_variable(name).expect("failed to get environment variable");
+        assert_eq!(value.as_deref(), Some(OsStr::new("")));
+
+        set_environment_variable(name, None).expect("failed to delete environment variable");
+        let value = get_environment_variable(name).expect("failed to get environment variable");
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn expand_environment_strings_checked_works() {
+        let defined = OsStr::new("SKYLIGHT_EXPAND_ENVIRONMENT_STRINGS_DEFINED");
+        set_environment_variable(defined, Some(OsStr::new("value")))
+            .expect("failed to set environment variable");
+
+        let input = OsStr::new(
+            "%SKYLIGHT_EXPAND_ENVIRONMENT_STRINGS_DEFINED%-%SKYLIGHT_EXPAND_ENVIRONMENT_STRINGS_UNDEFINED%",
+        );
+        let (output, unexpanded) =
+            expand_environment_strings_checked(input).expect("failed to expand");
+
+        set_environment_variable(defined, None).expect("failed to delete environment variable");
+
+        assert_eq!(
+            output,
+            "value-%SKYLIGHT_EXPAND_ENVIRONMENT_STRINGS_UNDEFINED%"
+        );
+        assert_eq!(
+            unexpanded,
+            [OsString::from(
+                "SKYLIGHT_EXPAND_ENVIRONMENT_STRINGS_UNDEFINED"
+            )]
+        );
+    }
+
+    #[test]
+    fn env_var_references_works() {
+        let references = |s: &str| -> Vec<String> {
+            let s: Vec<u16> = s.encode_utf16().collect();
+            env_var_references(&s)
+                .into_iter()
+                .map(String::from_utf16_lossy)
+                .collect()
+        };
+
+        assert_eq!(references("%A%-%B%"), ["%A%", "%B%"]);
+        assert_eq!(references("%A%B%"), ["%A%"]);
+        assert!(references("100%%").is_empty());
+        assert!(references("no references").is_empty());
+    }
+
+    #[test]
+    fn output_debug_string_smoke() {
+        // Capturing debugger output is not feasible here, so this only checks that it does not panic.
+        output_debug_string("skylight output_debug_string test\n".as_ref());
+        output_debug_str("skylight output_debug_str test\n");
+        output_debug_str("");
+    }
 }
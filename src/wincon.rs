@@ -0,0 +1,70 @@
+use winapi::shared::minwindef::FALSE;
+use winapi::um::consoleapi::AllocConsole;
+use winapi::um::wincon::AttachConsole;
+use winapi::um::wincon::FreeConsole;
+
+/// Pass this to [`attach_console`] to attach to the console of the parent process.
+pub const ATTACH_PARENT_PROCESS: u32 = winapi::um::wincon::ATTACH_PARENT_PROCESS;
+
+/// Allocate a new console for this process.
+///
+/// # Errors
+/// Returns an error if a console could not be allocated.
+/// A process can only have one console, so this fails with `ERROR_ACCESS_DENIED` if this process already has one.
+pub fn alloc_console() -> std::io::Result<()> {
+    let ret = unsafe { AllocConsole() };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Detach this process from its console.
+///
+/// # Errors
+/// Returns an error if this process could not be detached from its console.
+pub fn free_console() -> std::io::Result<()> {
+    let ret = unsafe { FreeConsole() };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Attach this process to the console of the process with the given pid.
+///
+/// Use [`ATTACH_PARENT_PROCESS`] to attach to the console of the parent process.
+///
+/// # Errors
+/// Returns an error if the console could not be attached.
+/// This fails with `ERROR_ACCESS_DENIED` if this process already has a console,
+/// or `ERROR_INVALID_HANDLE` if the process does not have a console.
+pub fn attach_console(pid: u32) -> std::io::Result<()> {
+    let ret = unsafe { AttachConsole(pid) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use winapi::shared::winerror::ERROR_ACCESS_DENIED;
+
+    #[test]
+    fn alloc_free_console() {
+        match alloc_console() {
+            Ok(()) => free_console().expect("failed to free console"),
+            // This process already has a console, which should not be freed.
+            Err(e) if e.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => {}
+            Err(e) => panic!("failed to alloc console: {}", e),
+        }
+    }
+}
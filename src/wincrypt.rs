@@ -1,10 +1,24 @@
 use crate::winbase::LocalWideString;
 use std::convert::TryInto;
+use std::ffi::OsStr;
 use std::mem::ManuallyDrop;
 use std::mem::MaybeUninit;
+use std::os::windows::ffi::OsStrExt;
 use std::ptr::NonNull;
+use winapi::shared::bcrypt::BCryptGenRandom;
+use winapi::shared::bcrypt::BCRYPT_USE_SYSTEM_PREFERRED_RNG;
+use winapi::shared::minwindef::DWORD;
 use winapi::shared::minwindef::FALSE;
+use winapi::um::dpapi::CryptProtectData;
+use winapi::um::dpapi::CryptProtectMemory;
 use winapi::um::dpapi::CryptUnprotectData;
+use winapi::um::dpapi::CryptUnprotectMemory;
+use winapi::um::dpapi::CRYPTPROTECTMEMORY_BLOCK_SIZE;
+use winapi::um::dpapi::CRYPTPROTECTMEMORY_CROSS_PROCESS;
+use winapi::um::dpapi::CRYPTPROTECTMEMORY_SAME_LOGON;
+use winapi::um::dpapi::CRYPTPROTECTMEMORY_SAME_PROCESS;
+use winapi::um::dpapi::CRYPTPROTECT_AUDIT;
+use winapi::um::dpapi::CRYPTPROTECT_LOCAL_MACHINE;
 use winapi::um::dpapi::CRYPTPROTECT_UI_FORBIDDEN;
 use winapi::um::{
     winbase::{LocalAlloc, LocalFree},
@@ -12,15 +26,73 @@ use winapi::um::{
 };
 
 /// A wincrypt DataBlob.
-#[repr(transparent)]
-pub struct DataBlob(DATA_BLOB);
+///
+/// A secure [`DataBlob`] overwrites its buffer with zeros before it is freed.
+/// This should be used for blobs that hold secrets, like decrypted data.
+///
+/// Most blobs own their buffer, which is allocated with `LocalAlloc` and freed with `LocalFree`.
+/// A blob made with [`DataBlob::borrow_slice`] instead points to memory owned by the caller,
+/// which is never written to, zeroed, or freed by the blob.
+pub struct DataBlob {
+    blob: DATA_BLOB,
+    secure: bool,
+    owns_buffer: bool,
+}
 
 impl DataBlob {
+    /// Make a [`DataBlob`] from a `DATA_BLOB`.
+    ///
+    /// # Safety
+    /// `blob.pbData` must be allocated with `LocalAlloc` and point to `blob.cbData` bytes.
+    unsafe fn from_raw(blob: DATA_BLOB, secure: bool) -> Self {
+        Self {
+            blob,
+            secure,
+            owns_buffer: true,
+        }
+    }
+
+    /// Make a [`DataBlob`] that points to a byte slice, without copying it.
+    ///
+    /// The blob does not own the slice, so it will not free it when dropped.
+    /// This is useful for passing data to functions like `CryptProtectData`, which do not take ownership of their input.
+    ///
+    /// # Safety
+    /// * `data` must outlive the returned blob.
+    /// * The returned blob must not be used to mutate `data`, like with [`DataBlob::as_mut_slice`] or a function writing through [`DataBlob::as_mut_ptr`].
+    ///
+    /// # Panics
+    /// Panics if `data.len() > u32::MAX`.
+    pub unsafe fn borrow_slice(data: &[u8]) -> Self {
+        Self {
+            blob: DATA_BLOB {
+                cbData: data.len().try_into().expect("data.len() > u32::MAX"),
+                pbData: data.as_ptr() as *mut u8,
+            },
+            secure: false,
+            owns_buffer: false,
+        }
+    }
+
     /// Make a [`DATA_BLOB`] from a byte slice.
     ///
     /// # Panics
     /// Panics if `data.len() > u32::MAX`.
     pub fn from_slice(data: &[u8]) -> Self {
+        Self::from_slice_inner(data, false)
+    }
+
+    /// Make a secure [`DATA_BLOB`] from a byte slice.
+    ///
+    /// The buffer will be overwritten with zeros before it is freed.
+    ///
+    /// # Panics
+    /// Panics if `data.len() > u32::MAX`.
+    pub fn from_slice_secure(data: &[u8]) -> Self {
+        Self::from_slice_inner(data, true)
+    }
+
+    fn from_slice_inner(data: &[u8], secure: bool) -> Self {
         let len = data.len();
         let len_u32: u32 = len.try_into().expect("data.len() > u32::MAX");
 
@@ -33,16 +105,26 @@ impl DataBlob {
         };
 
         let mut blob: MaybeUninit<DATA_BLOB> = MaybeUninit::uninit();
-        Self(unsafe {
+        unsafe {
             (*blob.as_mut_ptr()).cbData = len_u32;
             (*blob.as_mut_ptr()).pbData = buffer_ptr;
-            blob.assume_init()
-        })
+            Self::from_raw(blob.assume_init(), secure)
+        }
     }
 
     /// Get a mut ptr to the inner value
     pub fn as_mut_ptr(&mut self) -> *mut DATA_BLOB {
-        &mut self.0
+        &mut self.blob
+    }
+
+    /// Check if this blob owns its buffer, and will free it when dropped.
+    pub fn owns_buffer(&self) -> bool {
+        self.owns_buffer
+    }
+
+    /// Check if this blob will be overwritten with zeros before it is freed.
+    pub fn is_secure(&self) -> bool {
+        self.secure
     }
 
     /// Get the length of this blob
@@ -50,7 +132,7 @@ impl DataBlob {
     /// # Panics
     /// Panics if the length cannot fit in a `usize`.
     pub fn len(&self) -> usize {
-        self.0
+        self.blob
             .cbData
             .try_into()
             .expect("cannot fit length in a `usize`")
@@ -66,16 +148,87 @@ impl DataBlob {
 
     /// Get this blob as a byte slice.
     pub fn as_slice(&self) -> &[u8] {
-        unsafe { std::slice::from_raw_parts(self.0.pbData, self.len()) }
+        unsafe { std::slice::from_raw_parts(self.blob.pbData, self.len()) }
+    }
+
+    /// Get this blob as a mutable byte slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.blob.pbData, self.len()) }
+    }
+
+    /// Get a reader over the bytes of this blob.
+    pub fn reader(&self) -> std::io::Cursor<&[u8]> {
+        std::io::Cursor::new(self.as_slice())
+    }
+
+    /// Format this blob as a canonical hex dump.
+    ///
+    /// Each line holds 16 bytes, formatted as an offset, the bytes in hex, and the bytes as ASCII.
+    /// Bytes that are not printable ASCII are shown as `.`.
+    pub fn hex_dump(&self) -> String {
+        use std::fmt::Write;
+
+        let mut dump = String::new();
+        for (i, chunk) in self.as_slice().chunks(HEX_DUMP_LINE_LEN).enumerate() {
+            // Writing to a `String` cannot fail.
+            let _ = write!(dump, "{:08x}  ", i * HEX_DUMP_LINE_LEN);
+            for j in 0..HEX_DUMP_LINE_LEN {
+                match chunk.get(j) {
+                    Some(b) => {
+                        let _ = write!(dump, "{:02x} ", b);
+                    }
+                    None => dump.push_str("   "),
+                }
+
+                if j == (HEX_DUMP_LINE_LEN / 2) - 1 {
+                    dump.push(' ');
+                }
+            }
+
+            dump.push_str(" |");
+            dump.extend(chunk.iter().map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    char::from(b)
+                } else {
+                    '.'
+                }
+            }));
+            dump.push_str("|\n");
+        }
+
+        dump
+    }
+
+    /// Overwrite the buffer with zeros.
+    ///
+    /// Volatile writes are used so that this is not optimized away, even though the buffer is about to be freed.
+    fn zero(&mut self) {
+        let ptr = self.blob.pbData;
+        for i in 0..self.len() {
+            unsafe {
+                std::ptr::write_volatile(ptr.add(i), 0);
+            }
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
     }
 
     /// Try to destroy this object.
     ///
+    /// If this blob is secure, the buffer is overwritten with zeros first.
+    /// If this blob does not own its buffer, this does nothing.
+    ///
     /// # Errors
     /// Returns a tuple of this object and an error if this object could not be destroyed.
     pub fn destroy(self) -> Result<(), (Self, std::io::Error)> {
         let mut blob = ManuallyDrop::new(self);
-        let ret = unsafe { LocalFree(blob.as_mut_ptr().cast()) };
+        if !blob.owns_buffer {
+            return Ok(());
+        }
+
+        if blob.secure {
+            blob.zero();
+        }
+        let ret = unsafe { LocalFree(blob.blob.pbData.cast()) };
 
         if ret.is_null() {
             Ok(())
@@ -94,11 +247,44 @@ impl AsRef<[u8]> for DataBlob {
     }
 }
 
+impl AsMut<[u8]> for DataBlob {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
 impl std::fmt::Debug for DataBlob {
+    /// The contents of secure blobs, like decrypted data, are redacted.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("DataBlob")
-            .field("data", &self.as_slice())
-            .finish()
+        let mut debug = f.debug_struct("DataBlob");
+        debug.field("len", &self.len());
+        if self.is_secure() {
+            debug.field("data", &format_args!("<redacted>"));
+        } else {
+            debug.field("data", &TruncatedBytes(self.as_slice()));
+        }
+        debug.finish()
+    }
+}
+
+/// The number of bytes on each line of [`DataBlob::hex_dump`].
+const HEX_DUMP_LINE_LEN: usize = 16;
+
+/// The max number of bytes of a [`DataBlob`] to show in its `Debug` impl.
+const DEBUG_MAX_LEN: usize = 64;
+
+/// A byte slice that is truncated to [`DEBUG_MAX_LEN`] bytes in its `Debug` impl.
+struct TruncatedBytes<'a>(&'a [u8]);
+
+impl std::fmt::Debug for TruncatedBytes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+        list.entries(self.0.iter().take(DEBUG_MAX_LEN));
+        if self.0.len() > DEBUG_MAX_LEN {
+            list.finish_non_exhaustive()
+        } else {
+            list.finish()
+        }
     }
 }
 
@@ -116,30 +302,59 @@ impl From<&Vec<u8>> for DataBlob {
 
 impl Drop for DataBlob {
     fn drop(&mut self) {
-        std::mem::forget(Self(self.0).destroy());
+        let blob = Self {
+            blob: self.blob,
+            secure: self.secure,
+            owns_buffer: self.owns_buffer,
+        };
+        std::mem::forget(blob.destroy());
     }
 }
 
 /// Data decrypted with [`crypt_unprotect_data`].
 #[derive(Debug)]
 pub struct DecryptedData {
-    /// The decrypted data
+    /// The decrypted data.
+    ///
+    /// This blob is secure, so it is overwritten with zeros before it is freed.
     pub decrypted: DataBlob,
 
     /// The description of the decrypted data
     pub description: Option<LocalWideString>,
 }
 
+/// Make a borrowed `DATA_BLOB` for passing optional entropy to DPAPI functions.
+///
+/// DPAPI does not modify the entropy, so the blob may point to immutable data.
+///
+/// # Panics
+/// Panics if `entropy.len() > u32::MAX`.
+fn entropy_blob(entropy: &[u8]) -> DATA_BLOB {
+    DATA_BLOB {
+        cbData: entropy.len().try_into().expect("entropy.len() > u32::MAX"),
+        pbData: entropy.as_ptr() as *mut u8,
+    }
+}
+
 /// Decrypt data encrypted with `CryptProtectData`.
 ///
+/// If entropy was used to encrypt the data, the same entropy must be provided to decrypt it.
+///
 /// # Errors
-/// Returns an error if the data could not be decrypted.
-pub fn crypt_unprotect_data<E>(encrypted: E) -> std::io::Result<DecryptedData>
+/// Returns an error if the data could not be decrypted, including if the entropy does not match.
+///
+/// # Panics
+/// Panics if `entropy.len() > u32::MAX`.
+pub fn crypt_unprotect_data<E>(
+    encrypted: E,
+    entropy: Option<&[u8]>,
+) -> std::io::Result<DecryptedData>
 where
     E: Into<DataBlob>,
 {
     let mut encrypted = encrypted.into();
-    let mut decrypted: MaybeUninit<DataBlob> = MaybeUninit::zeroed();
+    let mut decrypted: MaybeUninit<DATA_BLOB> = MaybeUninit::zeroed();
+    let mut entropy = entropy.map(entropy_blob);
 
     let mut description_ptr = std::ptr::null_mut();
 
@@ -147,11 +362,13 @@ where
         CryptUnprotectData(
             encrypted.as_mut_ptr(),
             &mut description_ptr,
-            std::ptr::null_mut(),
+            entropy
+                .as_mut()
+                .map_or(std::ptr::null_mut(), |entropy| entropy),
             std::ptr::null_mut(),
             std::ptr::null_mut(),
             CRYPTPROTECT_UI_FORBIDDEN,
-            decrypted.as_mut_ptr().cast(),
+            decrypted.as_mut_ptr(),
         )
     };
 
@@ -163,7 +380,397 @@ where
     }
 
     Ok(DecryptedData {
-        decrypted: unsafe { decrypted.assume_init() },
+        decrypted: unsafe { DataBlob::from_raw(decrypted.assume_init(), true) },
         description,
     })
 }
+
+/// Decrypt bytes encrypted with `CryptProtectData`, like DPAPI-protected blobs read from a file.
+///
+/// This is a convenience wrapper around [`crypt_unprotect_data`] for when only the decrypted bytes are needed.
+/// The decrypted data is copied into a [`Vec`], and the secure [`DataBlob`] it came from is zeroed and freed.
+/// Use [`crypt_unprotect_data`] to get the description or to pass entropy.
+///
+/// # Errors
+/// Returns an error if the data could not be decrypted.
+pub fn unprotect_bytes(encrypted: &[u8]) -> std::io::Result<Vec<u8>> {
+    // # Safety
+    // `encrypted` outlives the blob, which is dropped inside `crypt_unprotect_data`.
+    // `CryptUnprotectData` does not write to its input.
+    let encrypted = unsafe { DataBlob::borrow_slice(encrypted) };
+    let decrypted = crypt_unprotect_data(encrypted, None)?;
+    Ok(decrypted.decrypted.as_slice().to_vec())
+}
+
+bitflags::bitflags! {
+    /// Flags for protecting data with [`crypt_protect_data`].
+    ///
+    pub struct CryptProtectFlags: DWORD {
+        /// Fail instead of displaying a UI.
+        ///
+        const UI_FORBIDDEN = CRYPTPROTECT_UI_FORBIDDEN;
+
+        /// Allow any user on the local machine to decrypt the data, instead of only the current user.
+        ///
+        const LOCAL_MACHINE = CRYPTPROTECT_LOCAL_MACHINE;
+
+        /// Generate an audit on protect and unprotect operations.
+        ///
+        const AUDIT = CRYPTPROTECT_AUDIT;
+    }
+}
+
+/// Encrypt data with `CryptProtectData`.
+///
+/// The data can be decrypted with [`crypt_unprotect_data`].
+/// If entropy is provided, the same entropy must be provided to decrypt the data.
+///
+/// # Errors
+/// Returns an error if the data could not be encrypted.
+///
+/// # Panics
+/// Panics if `entropy.len() > u32::MAX`.
+pub fn crypt_protect_data<E>(
+    data: E,
+    description: Option<&OsStr>,
+    entropy: Option<&[u8]>,
+    flags: CryptProtectFlags,
+) -> std::io::Result<DataBlob>
+where
+    E: Into<DataBlob>,
+{
+    let mut data = data.into();
+    let mut encrypted: MaybeUninit<DATA_BLOB> = MaybeUninit::zeroed();
+    let mut entropy = entropy.map(entropy_blob);
+
+    let description = description.map(|description| {
+        description
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>()
+    });
+
+    let ret = unsafe {
+        CryptProtectData(
+            data.as_mut_ptr(),
+            description
+                .as_ref()
+                .map_or(std::ptr::null(), |description| description.as_ptr()),
+            entropy
+                .as_mut()
+                .map_or(std::ptr::null_mut(), |entropy| entropy),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            flags.bits(),
+            encrypted.as_mut_ptr(),
+        )
+    };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(unsafe { DataBlob::from_raw(encrypted.assume_init(), false) })
+}
+
+/// Which processes may unprotect memory protected with [`protect_memory`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CryptMemoryFlags {
+    /// Only the current process may unprotect the memory.
+    SameProcess,
+
+    /// Any process may unprotect the memory.
+    CrossProcess,
+
+    /// Any process running as the same user in the same logon session may unprotect the memory.
+    SameLogon,
+}
+
+impl From<CryptMemoryFlags> for DWORD {
+    fn from(flags: CryptMemoryFlags) -> Self {
+        match flags {
+            CryptMemoryFlags::SameProcess => CRYPTPROTECTMEMORY_SAME_PROCESS,
+            CryptMemoryFlags::CrossProcess => CRYPTPROTECTMEMORY_CROSS_PROCESS,
+            CryptMemoryFlags::SameLogon => CRYPTPROTECTMEMORY_SAME_LOGON,
+        }
+    }
+}
+
+/// Get the length of a buffer for `CryptProtectMemory` or `CryptUnprotectMemory`, validating it.
+fn crypt_memory_len(data: &[u8]) -> std::io::Result<DWORD> {
+    if !data
+        .len()
+        .is_multiple_of(CRYPTPROTECTMEMORY_BLOCK_SIZE as usize)
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the buffer length is not a multiple of `CRYPTPROTECTMEMORY_BLOCK_SIZE`",
+        ));
+    }
+
+    data.len()
+        .try_into()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+/// Encrypt memory in place with `CryptProtectMemory`.
+///
+/// The memory can be decrypted in place with [`unprotect_memory`], using the same flags.
+///
+/// # Errors
+/// Returns an error if the length of `data` is not a multiple of `CRYPTPROTECTMEMORY_BLOCK_SIZE` (16),
+/// or if the memory could not be encrypted.
+pub fn protect_memory(data: &mut [u8], flags: CryptMemoryFlags) -> std::io::Result<()> {
+    let len = crypt_memory_len(data)?;
+    let ret = unsafe { CryptProtectMemory(data.as_mut_ptr().cast(), len, flags.into()) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Decrypt memory encrypted with [`protect_memory`] in place with `CryptUnprotectMemory`.
+///
+/// # Errors
+/// Returns an error if the length of `data` is not a multiple of `CRYPTPROTECTMEMORY_BLOCK_SIZE` (16),
+/// or if the memory could not be decrypted.
+pub fn unprotect_memory(data: &mut [u8], flags: CryptMemoryFlags) -> std::io::Result<()> {
+    let len = crypt_memory_len(data)?;
+    let ret = unsafe { CryptUnprotectMemory(data.as_mut_ptr().cast(), len, flags.into()) };
+
+    if ret == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Fill a buffer with cryptographically secure random bytes.
+///
+/// This uses `BCryptGenRandom` with the system-preferred RNG, so no algorithm handle or context is needed.
+///
+/// # Errors
+/// Returns an error if the random bytes could not be generated.
+pub fn gen_random(buf: &mut [u8]) -> std::io::Result<()> {
+    // `BCryptGenRandom` takes a `u32` length, so fill large buffers in chunks.
+    for chunk in buf.chunks_mut(u32::MAX as usize) {
+        let status = unsafe {
+            BCryptGenRandom(
+                std::ptr::null_mut(),
+                chunk.as_mut_ptr(),
+                chunk.len() as u32,
+                BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+            )
+        };
+
+        if status < 0 {
+            return Err(std::io::Error::other(format!(
+                "BCryptGenRandom failed with NTSTATUS {:#010X}",
+                status
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crypt_protect_data_round_trip() {
+        let data = b"Hello World!";
+        let description = "test description";
+
+        let encrypted = crypt_protect_data(
+            &data[..],
+            Some(description.as_ref()),
+            None,
+            CryptProtectFlags::UI_FORBIDDEN,
+        )
+        .expect("failed to encrypt");
+        assert_ne!(encrypted.as_slice(), &data[..]);
+
+        let decrypted =
+            crypt_unprotect_data(encrypted.as_slice(), None).expect("failed to decrypt");
+        assert_eq!(decrypted.decrypted.as_slice(), &data[..]);
+        assert_eq!(
+            decrypted
+                .description
+                .expect("missing description")
+                .as_os_string(),
+            description
+        );
+    }
+
+    #[test]
+    fn unprotect_bytes_round_trip() {
+        let data = b"Hello World!";
+
+        let encrypted = crypt_protect_data(&data[..], None, None, CryptProtectFlags::UI_FORBIDDEN)
+            .expect("failed to encrypt");
+        let decrypted = unprotect_bytes(encrypted.as_slice()).expect("failed to decrypt");
+        assert_eq!(decrypted, data);
+
+        assert!(unprotect_bytes(b"not encrypted").is_err());
+    }
+
+    #[test]
+    fn crypt_protect_data_entropy() {
+        let data = b"Hello World!";
+        let entropy = b"entropy";
+
+        let encrypted = crypt_protect_data(
+            &data[..],
+            None,
+            Some(entropy),
+            CryptProtectFlags::UI_FORBIDDEN,
+        )
+        .expect("failed to encrypt");
+
+        assert!(crypt_unprotect_data(encrypted.as_slice(), Some(b"wrong entropy")).is_err());
+        assert!(crypt_unprotect_data(encrypted.as_slice(), None).is_err());
+
+        let decrypted =
+            crypt_unprotect_data(encrypted.as_slice(), Some(entropy)).expect("failed to decrypt");
+        assert_eq!(decrypted.decrypted.as_slice(), &data[..]);
+    }
+
+    #[test]
+    fn data_blob_secure_zero() {
+        let data = b"secret";
+        let mut blob = DataBlob::from_slice_secure(&data[..]);
+        assert!(blob.is_secure());
+        assert_eq!(blob.as_slice(), &data[..]);
+
+        blob.zero();
+        assert!(blob.as_slice().iter().all(|b| *b == 0));
+        assert_eq!(blob.len(), data.len());
+    }
+
+    #[test]
+    fn data_blob_owned() {
+        let data = b"Hello World!";
+        let blob = DataBlob::from_slice(&data[..]);
+        assert!(blob.owns_buffer());
+        assert_ne!(blob.as_slice().as_ptr(), data.as_ptr());
+        assert_eq!(blob.as_slice(), &data[..]);
+        blob.destroy()
+            .map_err(|(_, e)| e)
+            .expect("failed to destroy");
+    }
+
+    #[test]
+    fn data_blob_borrowed() {
+        let data = b"Hello World!";
+        let blob = unsafe { DataBlob::borrow_slice(&data[..]) };
+        assert!(!blob.owns_buffer());
+        assert!(!blob.is_secure());
+        assert_eq!(blob.as_slice().as_ptr(), data.as_ptr());
+        assert_eq!(blob.as_slice(), &data[..]);
+
+        let encrypted = crypt_protect_data(blob, None, None, CryptProtectFlags::UI_FORBIDDEN)
+            .expect("failed to encrypt");
+        let decrypted =
+            crypt_unprotect_data(encrypted.as_slice(), None).expect("failed to decrypt");
+        assert_eq!(decrypted.decrypted.as_slice(), &data[..]);
+
+        // The borrowed data must still be intact after the blob is dropped.
+        assert_eq!(data, b"Hello World!");
+    }
+
+    #[test]
+    fn data_blob_as_mut_slice() {
+        let mut blob = DataBlob::from_slice(b"Hello World!");
+        blob.as_mut_slice()[0] = b'J';
+        assert_eq!(blob.as_slice(), b"Jello World!");
+
+        blob.as_mut()[11] = b'?';
+        assert_eq!(blob.as_slice(), b"Jello World?");
+    }
+
+    #[test]
+    fn data_blob_reader() {
+        use std::io::Read;
+
+        let blob = DataBlob::from_slice(b"Hello World!");
+        let mut data = Vec::new();
+        blob.reader()
+            .read_to_end(&mut data)
+            .expect("failed to read");
+        assert_eq!(data, blob.as_slice());
+    }
+
+    #[test]
+    fn data_blob_hex_dump() {
+        let blob = DataBlob::from_slice(b"A\x00~");
+        assert_eq!(
+            blob.hex_dump(),
+            "00000000  41 00 7e                                          |A.~|\n"
+        );
+
+        let blob = DataBlob::from_slice(b"Hello, World!\x01\x02\x03XYZ");
+        assert_eq!(
+            blob.hex_dump(),
+            concat!(
+                "00000000  48 65 6c 6c 6f 2c 20 57  6f 72 6c 64 21 01 02 03  |Hello, World!...|\n",
+                "00000010  58 59 5a                                          |XYZ|\n",
+            )
+        );
+
+        let blob = DataBlob::from_slice(&[]);
+        assert_eq!(blob.hex_dump(), "");
+    }
+
+    #[test]
+    fn data_blob_debug_truncated() {
+        let blob = DataBlob::from_slice(&[0; 100]);
+        let expected = format!("DataBlob {{ len: 100, data: [{}..] }}", "0, ".repeat(64));
+        assert_eq!(format!("{:?}", blob), expected);
+    }
+
+    #[test]
+    fn data_blob_debug_secure_redacted() {
+        let blob = DataBlob::from_slice_secure(b"secret");
+        assert_eq!(
+            format!("{:?}", blob),
+            "DataBlob { len: 6, data: <redacted> }"
+        );
+    }
+
+    #[test]
+    fn protect_memory_round_trip() {
+        let data = *b"0123456789abcdef";
+        let mut buffer = data;
+
+        protect_memory(&mut buffer, CryptMemoryFlags::SameProcess).expect("failed to protect");
+        assert_ne!(buffer, data);
+
+        unprotect_memory(&mut buffer, CryptMemoryFlags::SameProcess).expect("failed to unprotect");
+        assert_eq!(buffer, data);
+    }
+
+    #[test]
+    fn protect_memory_invalid_len() {
+        let mut buffer = [0; 15];
+        let err = protect_memory(&mut buffer, CryptMemoryFlags::SameProcess)
+            .expect_err("protected an invalid length");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn gen_random_smoke() {
+        let mut buffer = [0; 32];
+        let mut last_buffer = [0; 32];
+
+        for _ in 0..4 {
+            gen_random(&mut buffer).expect("failed to generate random bytes");
+            assert!(buffer.iter().any(|b| *b != 0));
+            assert_ne!(buffer, last_buffer);
+            last_buffer = buffer;
+        }
+    }
+}
@@ -1,26 +1,292 @@
 use crate::HModule;
 use crate::LocalWideString;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
 use std::ptr::NonNull;
-use winapi::shared::ntdef::LANG_SYSTEM_DEFAULT;
-use winapi::shared::ntdef::MAKELANGID;
+use winapi::shared::ntdef::LANGID;
+use winapi::shared::ntdef::LANG_ENGLISH;
+use winapi::shared::ntdef::LANG_NEUTRAL;
+use winapi::shared::ntdef::NTSTATUS;
+use winapi::shared::ntdef::SUBLANG_DEFAULT;
+use winapi::shared::ntdef::SUBLANG_ENGLISH_US;
+use winapi::shared::ntdef::SUBLANG_NEUTRAL;
 use winapi::shared::ntdef::SUBLANG_SYS_DEFAULT;
+use winapi::shared::winerror::CO_E_NOTINITIALIZED;
+use winapi::shared::winerror::ERROR_FILE_NOT_FOUND;
+use winapi::shared::winerror::ERROR_NOT_ENOUGH_MEMORY;
+use winapi::shared::winerror::E_ACCESSDENIED;
+use winapi::shared::winerror::E_FAIL;
+use winapi::shared::winerror::E_INVALIDARG;
+use winapi::shared::winerror::E_NOINTERFACE;
+use winapi::shared::winerror::E_NOTIMPL;
+use winapi::shared::winerror::E_OUTOFMEMORY;
+use winapi::shared::winerror::E_POINTER;
+use winapi::shared::winerror::FACILITY_DISPATCH;
+use winapi::shared::winerror::FACILITY_ITF;
+use winapi::shared::winerror::FACILITY_NULL;
+use winapi::shared::winerror::FACILITY_RPC;
+use winapi::shared::winerror::FACILITY_STORAGE;
+use winapi::shared::winerror::FACILITY_WIN32;
+use winapi::shared::winerror::FAILED;
+use winapi::shared::winerror::HRESULT_CODE;
+use winapi::shared::winerror::HRESULT_FACILITY;
+use winapi::shared::winerror::HRESULT_FROM_NT;
+use winapi::shared::winerror::RPC_E_CHANGED_MODE;
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::shared::winerror::S_FALSE;
+use winapi::shared::winerror::S_OK;
 use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::winbase::FormatMessageW;
 use winapi::um::winbase::FORMAT_MESSAGE_ALLOCATE_BUFFER;
+use winapi::um::winbase::FORMAT_MESSAGE_ARGUMENT_ARRAY;
 use winapi::um::winbase::FORMAT_MESSAGE_FROM_HMODULE;
 use winapi::um::winbase::FORMAT_MESSAGE_FROM_SYSTEM;
 use winapi::um::winbase::FORMAT_MESSAGE_IGNORE_INSERTS;
 
+/// The highest insert number a message can reference, `%99`.
+const MAX_MESSAGE_INSERTS: usize = 99;
+
+/// The severity of an [`HResult`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Severity {
+    /// The operation succeeded
+    Success,
+
+    /// The operation failed
+    Failure,
+}
+
+/// The facility of an [`HResult`], the source of the error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Facility {
+    /// The default facility, for broadly applicable codes like `S_OK`
+    Null,
+
+    /// RPC errors
+    Rpc,
+
+    /// `IDispatch` errors
+    Dispatch,
+
+    /// `IStorage` and `IStream` errors
+    Storage,
+
+    /// Interface-specific errors
+    Itf,
+
+    /// Win32 error codes
+    Win32,
+
+    /// A facility this library does not know about
+    Other(u16),
+}
+
+impl From<u16> for Facility {
+    fn from(facility: u16) -> Self {
+        match i32::from(facility) {
+            FACILITY_NULL => Self::Null,
+            FACILITY_RPC => Self::Rpc,
+            FACILITY_DISPATCH => Self::Dispatch,
+            FACILITY_STORAGE => Self::Storage,
+            FACILITY_ITF => Self::Itf,
+            FACILITY_WIN32 => Self::Win32,
+            _ => Self::Other(facility),
+        }
+    }
+}
+
+impl From<Facility> for u16 {
+    fn from(facility: Facility) -> Self {
+        // `as` is fine here, as all facilities fit in a u16
+        match facility {
+            Facility::Null => FACILITY_NULL as u16,
+            Facility::Rpc => FACILITY_RPC as u16,
+            Facility::Dispatch => FACILITY_DISPATCH as u16,
+            Facility::Storage => FACILITY_STORAGE as u16,
+            Facility::Itf => FACILITY_ITF as u16,
+            Facility::Win32 => FACILITY_WIN32 as u16,
+            Facility::Other(facility) => facility,
+        }
+    }
+}
+
+/// A language ID, used to pick the language of an error message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Language(pub LANGID);
+
+impl Language {
+    /// Let `FormatMessageW` search for a message in a fallback order of languages.
+    pub const NEUTRAL: Self = Self::new(LANG_NEUTRAL, SUBLANG_NEUTRAL);
+
+    /// The default language of the system.
+    pub const SYSTEM_DEFAULT: Self = Self::new(LANG_NEUTRAL, SUBLANG_SYS_DEFAULT);
+
+    /// The default language of the current user.
+    pub const USER_DEFAULT: Self = Self::new(LANG_NEUTRAL, SUBLANG_DEFAULT);
+
+    /// US English.
+    pub const ENGLISH_US: Self = Self::new(LANG_ENGLISH, SUBLANG_ENGLISH_US);
+
+    /// Make a [`Language`] from a primary language ID and a sublanguage ID, like the `MAKELANGID` macro.
+    pub const fn new(primary: u16, sub: u16) -> Self {
+        Self((sub << 10) | primary)
+    }
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self::SYSTEM_DEFAULT
+    }
+}
+
+/// A common failure, as returned by [`HResult::classify`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WellKnownError {
+    /// Access was denied
+    AccessDenied,
+
+    /// The file could not be found
+    FileNotFound,
+
+    /// One or more arguments are invalid
+    InvalidArg,
+
+    /// Failed to allocate memory
+    OutOfMemory,
+
+    /// COM was not initialized on this thread
+    NotInitialized,
+
+    /// COM was already initialized on this thread with a different concurrency model
+    ChangedMode,
+
+    /// Any other [`HResult`]
+    Other(HResult),
+}
+
 /// A wrapper for a windows HRESULT.
 #[derive(Eq, PartialEq, Clone, Copy, Hash)]
 pub struct HResult(pub u32);
 
 impl HResult {
+    // `as` is basically a safe transmute in these constants
+
+    /// The operation succeeded
+    pub const S_OK: Self = Self(S_OK as u32);
+
+    /// The operation succeeded, but returned a boolean false
+    pub const S_FALSE: Self = Self(S_FALSE as u32);
+
+    /// Unspecified failure
+    pub const E_FAIL: Self = Self(E_FAIL as u32);
+
+    /// Access was denied
+    pub const E_ACCESSDENIED: Self = Self(E_ACCESSDENIED as u32);
+
+    /// One or more arguments are invalid
+    pub const E_INVALIDARG: Self = Self(E_INVALIDARG as u32);
+
+    /// The interface is not supported
+    pub const E_NOINTERFACE: Self = Self(E_NOINTERFACE as u32);
+
+    /// An invalid pointer was used
+    pub const E_POINTER: Self = Self(E_POINTER as u32);
+
+    /// Failed to allocate memory
+    pub const E_OUTOFMEMORY: Self = Self(E_OUTOFMEMORY as u32);
+
+    /// The method is not implemented
+    pub const E_NOTIMPL: Self = Self(E_NOTIMPL as u32);
+
     /// Get the last error for this thread
+    ///
+    /// This wraps the raw Win32 error code without converting it into an HRESULT.
+    /// Use [`HResult::last_error`] to get it as an HRESULT.
     pub fn get_last_error() -> Self {
         Self::from(unsafe { GetLastError() })
     }
 
+    /// Make an [`HResult`] from a Win32 error code, like the `HRESULT_FROM_WIN32` macro.
+    ///
+    /// Values that are already HRESULTs (zero or with the high bit set) are passed through unchanged.
+    pub fn from_win32(code: u32) -> Self {
+        // `as` is basically a safe transmute here
+        if code as i32 <= 0 {
+            Self(code)
+        } else {
+            Self((code & 0x0000_FFFF) | ((FACILITY_WIN32 as u32) << 16) | 0x8000_0000)
+        }
+    }
+
+    /// Get the last error for this thread as an HRESULT, using [`HResult::from_win32`].
+    pub fn last_error() -> Self {
+        Self::from_win32(unsafe { GetLastError() })
+    }
+
+    /// Classify this HRESULT as a [`WellKnownError`], for matching on common failures.
+    ///
+    /// Win32 errors are only recognized as failures in the Win32 facility,
+    /// so use [`HResult::last_error`] rather than [`HResult::get_last_error`] to classify the last error.
+    pub fn classify(&self) -> WellKnownError {
+        let hr = *self;
+
+        if hr == Self::E_ACCESSDENIED {
+            WellKnownError::AccessDenied
+        } else if hr == Self::from_win32(ERROR_FILE_NOT_FOUND) {
+            WellKnownError::FileNotFound
+        } else if hr == Self::E_INVALIDARG {
+            WellKnownError::InvalidArg
+        } else if hr == Self::E_OUTOFMEMORY || hr == Self::from_win32(ERROR_NOT_ENOUGH_MEMORY) {
+            WellKnownError::OutOfMemory
+        } else if hr == Self(CO_E_NOTINITIALIZED as u32) {
+            WellKnownError::NotInitialized
+        } else if hr == Self(RPC_E_CHANGED_MODE as u32) {
+            WellKnownError::ChangedMode
+        } else {
+            WellKnownError::Other(*self)
+        }
+    }
+
+    /// Get the severity of this HRESULT, from the high bit.
+    pub fn severity(&self) -> Severity {
+        if self.is_success() {
+            Severity::Success
+        } else {
+            Severity::Failure
+        }
+    }
+
+    /// Get the facility code of this HRESULT.
+    ///
+    /// Use [`Facility::from`] to map this to a known facility.
+    pub fn facility(&self) -> u16 {
+        // `as` is fine here, as the facility is masked to 13 bits
+        HRESULT_FACILITY(self.0 as i32) as u16
+    }
+
+    /// Get the code of this HRESULT, the low 16 bits.
+    pub fn code(&self) -> u16 {
+        // `as` is fine here, as the code is masked to 16 bits
+        HRESULT_CODE(self.0 as i32) as u16
+    }
+
+    /// Check if this HRESULT represents success.
+    pub fn is_success(&self) -> bool {
+        SUCCEEDED(self.0 as i32)
+    }
+
+    /// Check if this HRESULT represents failure.
+    pub fn is_failure(&self) -> bool {
+        FAILED(self.0 as i32)
+    }
+
+    /// Get a formatter that displays this HRESULT as raw numbers, like `HRESULT(0x80070002)`.
+    ///
+    /// Unlike the [`std::fmt::Display`] impl of [`HResult`], this does not look up the message, so it never allocates or fails.
+    pub fn display_raw(&self) -> HResultRawDisplay {
+        HResultRawDisplay(*self)
+    }
+
     /// Get the message for this error using default settings.
     pub fn message(&self) -> std::io::Result<LocalWideString> {
         self.message_with_hmodule(None)
@@ -32,6 +298,42 @@ impl HResult {
     pub fn message_with_hmodule(
         &self,
         module: Option<&HModule>,
+    ) -> std::io::Result<LocalWideString> {
+        self.format_message(module, Language::default(), None)
+    }
+
+    /// Get the message for this error in the given language, optionally loading definitions from a given dll.
+    ///
+    /// The dll must be loaded in this process when this function is called.
+    ///
+    /// Unless [`Language::NEUTRAL`] is used, this will not fall back to another language.
+    ///
+    /// # Errors
+    /// Returns an error with the code `ERROR_RESOURCE_LANG_NOT_FOUND` if the message is not available in the given language.
+    pub fn message_with_options(
+        &self,
+        module: Option<&HModule>,
+        language: Language,
+    ) -> std::io::Result<LocalWideString> {
+        self.format_message(module, language, None)
+    }
+
+    /// Get the message for this error, replacing inserts like `%1` and `%2` with the given arguments.
+    ///
+    /// `args[0]` replaces `%1`, `args[1]` replaces `%2`, and so on.
+    /// Inserts without a matching argument are replaced with an empty string.
+    pub fn message_with_args(&self, args: &[&OsStr]) -> std::io::Result<LocalWideString> {
+        self.format_message(None, Language::default(), Some(args))
+    }
+
+    /// Format the message for this error with `FormatMessageW`.
+    ///
+    /// Inserts are ignored if `args` is `None`.
+    fn format_message(
+        &self,
+        module: Option<&HModule>,
+        language: Language,
+        args: Option<&[&OsStr]>,
     ) -> std::io::Result<LocalWideString> {
         let mut flags = 0;
 
@@ -39,22 +341,44 @@ impl HResult {
             flags |= FORMAT_MESSAGE_FROM_HMODULE;
         }
 
+        // The wide strings must outlive the array of pointers to them.
+        let wide_args: Option<Vec<Vec<u16>>> = args.map(|args| {
+            args.iter()
+                .map(|arg| arg.encode_wide().chain(std::iter::once(0)).collect())
+                .collect()
+        });
+        // A message may reference up to `%99`, so pad the array to make every insert point to a valid string.
+        let empty: [u16; 1] = [0];
+        let mut arg_ptrs: Option<Vec<usize>> = wide_args.as_ref().map(|wide_args| {
+            let mut arg_ptrs: Vec<usize> =
+                wide_args.iter().map(|arg| arg.as_ptr() as usize).collect();
+            if arg_ptrs.len() < MAX_MESSAGE_INSERTS {
+                arg_ptrs.resize(MAX_MESSAGE_INSERTS, empty.as_ptr() as usize);
+            }
+            arg_ptrs
+        });
+
+        if arg_ptrs.is_some() {
+            flags |= FORMAT_MESSAGE_ARGUMENT_ARRAY;
+        } else {
+            flags |= FORMAT_MESSAGE_IGNORE_INSERTS;
+        }
+
         let mut ptr: *mut u16 = std::ptr::null_mut();
         let size = unsafe {
             FormatMessageW(
-                flags
-                    | FORMAT_MESSAGE_ALLOCATE_BUFFER
-                    | FORMAT_MESSAGE_FROM_SYSTEM
-                    | FORMAT_MESSAGE_IGNORE_INSERTS,
+                flags | FORMAT_MESSAGE_ALLOCATE_BUFFER | FORMAT_MESSAGE_FROM_SYSTEM,
                 module
                     .map(|hmodule| hmodule.as_raw())
                     .unwrap_or(std::ptr::null_mut())
                     .cast(),
                 self.0,
-                MAKELANGID(LANG_SYSTEM_DEFAULT, SUBLANG_SYS_DEFAULT).into(),
+                language.0.into(),
                 std::mem::transmute(&mut ptr), // This param is a *mut u16, but needs to accept a *mut *mut u16 since we sepcify the FORMAT_MESSAGE_ALLOCATE_BUFFER flag.
                 0,
-                std::ptr::null_mut(),
+                arg_ptrs.as_mut().map_or(std::ptr::null_mut(), |arg_ptrs| {
+                    arg_ptrs.as_mut_ptr().cast()
+                }),
             )
         };
 
@@ -63,7 +387,7 @@ impl HResult {
         }
 
         let ptr = NonNull::new(ptr).expect("ptr is null");
-        let ret = unsafe { LocalWideString::from_raw(ptr) };
+        let ret = unsafe { LocalWideString::from_raw_with_len(ptr, size as usize) };
 
         Ok(ret)
     }
@@ -83,21 +407,56 @@ impl From<i32> for HResult {
 }
 
 impl From<HResult> for std::io::Error {
+    /// Failures in the Win32 facility are unwrapped into their Win32 error code,
+    /// so that [`std::io::Error::kind`] works and round-trips through `From<std::io::Error> for HResult` are lossless.
     fn from(result: HResult) -> Self {
+        if result.is_failure() && Facility::from(result.facility()) == Facility::Win32 {
+            return std::io::Error::from_raw_os_error(result.code().into());
+        }
+
         // `as` is basically a safe transmute here
         std::io::Error::from_raw_os_error(result.0 as i32)
     }
 }
 
+impl From<std::io::Error> for HResult {
+    /// OS errors are converted with [`HResult::from_win32`], while all other errors become `E_FAIL`.
+    fn from(error: std::io::Error) -> Self {
+        match error.raw_os_error() {
+            // `as` is basically a safe transmute here
+            Some(code) => Self::from_win32(code as u32),
+            None => Self::E_FAIL,
+        }
+    }
+}
+
 impl std::fmt::Display for HResult {
+    /// If the message cannot be found, this falls back to [`HResult::display_raw`].
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.message() {
             Ok(msg) => msg.display().fmt(f),
-            Err(e) => e.fmt(f),
+            Err(_) => self.display_raw().fmt(f),
         }
     }
 }
 
+/// A formatter that displays an [`HResult`] as raw numbers, made with [`HResult::display_raw`].
+#[derive(Debug, Copy, Clone)]
+pub struct HResultRawDisplay(HResult);
+
+impl std::fmt::Display for HResultRawDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "HRESULT(0x{:08X}) [severity: {:?}, facility: {}, code: {}]",
+            self.0 .0,
+            self.0.severity(),
+            self.0.facility(),
+            self.0.code()
+        )
+    }
+}
+
 impl std::fmt::Debug for HResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.message() {
@@ -109,43 +468,380 @@ impl std::fmt::Debug for HResult {
 
 impl std::error::Error for HResult {}
 
+/// The severity of an [`NtStatus`], from its top two bits.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum NtSeverity {
+    /// The operation succeeded
+    Success,
+
+    /// The operation succeeded, with extra information
+    Information,
+
+    /// The operation succeeded, with a warning
+    Warning,
+
+    /// The operation failed
+    Error,
+}
+
+/// A wrapper for an `NTSTATUS`, the status code returned by NT-level and BCrypt functions.
+#[derive(Eq, PartialEq, Clone, Copy, Hash)]
+pub struct NtStatus(pub NTSTATUS);
+
+impl NtStatus {
+    /// The operation succeeded
+    pub const SUCCESS: Self = Self(0);
+
+    /// Get the severity of this status, from the top two bits.
+    pub fn severity(&self) -> NtSeverity {
+        // `as` is basically a safe transmute here
+        match (self.0 as u32) >> 30 {
+            0 => NtSeverity::Success,
+            1 => NtSeverity::Information,
+            2 => NtSeverity::Warning,
+            _ => NtSeverity::Error,
+        }
+    }
+
+    /// Check if the severity of this status is success.
+    ///
+    /// Note that this is stricter than the `NT_SUCCESS` macro, which also accepts informational statuses.
+    pub fn is_success(&self) -> bool {
+        self.severity() == NtSeverity::Success
+    }
+
+    /// Check if the severity of this status is informational.
+    pub fn is_information(&self) -> bool {
+        self.severity() == NtSeverity::Information
+    }
+
+    /// Check if the severity of this status is a warning.
+    pub fn is_warning(&self) -> bool {
+        self.severity() == NtSeverity::Warning
+    }
+
+    /// Check if the severity of this status is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity() == NtSeverity::Error
+    }
+
+    /// Convert this status into an [`HResult`], like the `HRESULT_FROM_NT` macro.
+    pub fn to_hresult(&self) -> HResult {
+        // `as` is basically a safe transmute here
+        HResult::from(HRESULT_FROM_NT(self.0 as u32))
+    }
+
+    /// Get the message for this status.
+    ///
+    /// Messages for `NTSTATUS` codes are loaded from `ntdll.dll`.
+    pub fn message(&self) -> std::io::Result<LocalWideString> {
+        let ntdll = HModule::get_module_handle("ntdll.dll".as_ref())?;
+
+        // `as` is basically a safe transmute here
+        HResult(self.0 as u32).message_with_hmodule(Some(&ntdll))
+    }
+}
+
+impl From<NTSTATUS> for NtStatus {
+    fn from(status: NTSTATUS) -> Self {
+        Self(status)
+    }
+}
+
+impl std::fmt::Display for NtStatus {
+    /// If the message cannot be found, this falls back to the raw status code.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.message() {
+            Ok(msg) => msg.display().fmt(f),
+            // `as` is basically a safe transmute here
+            Err(_) => write!(f, "NTSTATUS(0x{:08X})", self.0 as u32),
+        }
+    }
+}
+
+impl std::fmt::Debug for NtStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // `as` is basically a safe transmute here
+        write!(f, "NtStatus(0x{:08X})", self.0 as u32)
+    }
+}
+
+impl std::error::Error for NtStatus {}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use winapi::shared::ntstatus::STATUS_ACCESS_VIOLATION;
+    use winapi::shared::ntstatus::STATUS_BUFFER_OVERFLOW;
+    use winapi::shared::ntstatus::STATUS_SUCCESS;
     use winapi::shared::winerror::CLASS_E_NOAGGREGATION;
     use winapi::shared::winerror::CO_E_NOTINITIALIZED;
+    use winapi::shared::winerror::ERROR_BAD_EXE_FORMAT;
+    use winapi::shared::winerror::ERROR_FILE_NOT_FOUND;
+    use winapi::shared::winerror::ERROR_RESOURCE_LANG_NOT_FOUND;
+    use winapi::shared::winerror::E_INVALIDARG;
     use winapi::shared::winerror::REGDB_E_CLASSNOTREG;
     use winapi::shared::winerror::RPC_E_CHANGED_MODE;
-    use winapi::shared::winerror::S_FALSE;
-    use winapi::shared::winerror::S_OK;
 
     #[test]
     fn display_s_ok() {
-        assert!(HResult::from(S_OK).message().is_ok());
+        let hr = HResult::from(S_OK);
+        assert_eq!(hr, HResult(0x0000_0000));
+        assert!(hr.message().is_ok());
     }
 
     #[test]
     fn display_s_false() {
-        assert!(HResult::from(S_FALSE).message().is_ok());
+        let hr = HResult::from(S_FALSE);
+        assert_eq!(hr, HResult(0x0000_0001));
+        assert!(hr.message().is_ok());
     }
 
     #[test]
     fn display_rpc_e_changed_mode() {
-        assert!(HResult::from(RPC_E_CHANGED_MODE).message().is_ok());
+        let hr = HResult::from(RPC_E_CHANGED_MODE);
+        assert_eq!(hr, HResult(0x8001_0106));
+        assert!(hr.message().is_ok());
     }
 
     #[test]
     fn display_co_e_not_initialized() {
-        assert!(HResult::from(CO_E_NOTINITIALIZED).message().is_ok());
+        let hr = HResult::from(CO_E_NOTINITIALIZED);
+        assert_eq!(hr, HResult(0x8004_01F0));
+        assert!(hr.message().is_ok());
     }
 
     #[test]
     fn display_regdb_e_class_not_reg() {
-        assert!(HResult::from(REGDB_E_CLASSNOTREG).message().is_ok());
+        let hr = HResult::from(REGDB_E_CLASSNOTREG);
+        assert_eq!(hr, HResult(0x8004_0154));
+        assert!(hr.message().is_ok());
     }
 
     #[test]
     fn display_class_e_no_aggregation() {
-        assert!(HResult::from(CLASS_E_NOAGGREGATION).message().is_ok());
+        let hr = HResult::from(CLASS_E_NOAGGREGATION);
+        assert_eq!(hr, HResult(0x8004_0110));
+        assert!(hr.message().is_ok());
+    }
+
+    #[test]
+    fn decompose_e_invalidarg() {
+        let hr = HResult::from(E_INVALIDARG);
+        assert_eq!(hr.severity(), Severity::Failure);
+        assert!(hr.is_failure());
+        assert!(!hr.is_success());
+        assert_eq!(Facility::from(hr.facility()), Facility::Win32);
+        assert_eq!(hr.code(), 0x0057);
+    }
+
+    #[test]
+    fn decompose_hresult_from_win32() {
+        // HRESULT_FROM_WIN32(ERROR_FILE_NOT_FOUND)
+        let hr = HResult::from(0x8007_0002_u32);
+        assert_eq!(hr.severity(), Severity::Failure);
+        assert_eq!(Facility::from(hr.facility()), Facility::Win32);
+        assert_eq!(u32::from(hr.code()), ERROR_FILE_NOT_FOUND);
+    }
+
+    #[test]
+    fn decompose_s_ok() {
+        let hr = HResult::from(S_OK);
+        assert_eq!(hr.severity(), Severity::Success);
+        assert_eq!(Facility::from(hr.facility()), Facility::Null);
+        assert_eq!(hr.code(), 0);
+    }
+
+    #[test]
+    fn from_win32() {
+        assert_eq!(
+            HResult::from_win32(ERROR_FILE_NOT_FOUND),
+            HResult(0x8007_0002)
+        );
+        assert_eq!(HResult::from_win32(0), HResult::from(S_OK));
+        assert_eq!(
+            HResult::from_win32(E_INVALIDARG as u32),
+            HResult::from(E_INVALIDARG)
+        );
+    }
+
+    #[test]
+    fn message_with_args() {
+        // "%1 is not a valid Win32 application."
+        let message = HResult::from(ERROR_BAD_EXE_FORMAT)
+            .message_with_args(&["skylight_test.exe".as_ref()])
+            .expect("failed to get message")
+            .to_str_lossy();
+        assert!(message.contains("skylight_test.exe"));
+        assert!(!message.contains("%1"));
+
+        // Missing arguments are replaced with an empty string.
+        let message = HResult::from(ERROR_BAD_EXE_FORMAT)
+            .message_with_args(&[])
+            .expect("failed to get message")
+            .to_str_lossy();
+        assert!(message.starts_with(" is not a valid"), "{}", message);
+    }
+
+    #[test]
+    fn message_with_options_english_us() {
+        match HResult::from(ERROR_FILE_NOT_FOUND).message_with_options(None, Language::ENGLISH_US) {
+            Ok(message) => {
+                let message = message.to_str_lossy();
+                assert!(message.contains("cannot find the file"), "{}", message);
+            }
+            // US English may not be installed.
+            Err(e) if e.raw_os_error() == Some(ERROR_RESOURCE_LANG_NOT_FOUND as i32) => {}
+            Err(e) => panic!("failed to get message: {}", e),
+        }
+    }
+
+    #[test]
+    fn message_with_options_default() {
+        let message = HResult::from(ERROR_FILE_NOT_FOUND)
+            .message_with_options(None, Language::default())
+            .expect("failed to get message");
+        let default_message = HResult::from(ERROR_FILE_NOT_FOUND)
+            .message()
+            .expect("failed to get message");
+        assert_eq!(message.as_slice(), default_message.as_slice());
+    }
+
+    #[test]
+    fn io_error_round_trip() {
+        let error = std::io::Error::from_raw_os_error(5);
+        let hr = HResult::from(error);
+        assert_eq!(hr, HResult(0x8007_0005));
+
+        let error = std::io::Error::from(hr);
+        assert_eq!(error.raw_os_error(), Some(5));
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+
+        assert_eq!(HResult::from(error), hr);
+    }
+
+    #[test]
+    fn io_error_non_os() {
+        let error = std::io::Error::other("not an os error");
+        assert_eq!(HResult::from(error), HResult::from(E_FAIL));
+    }
+
+    #[test]
+    fn named_constants() {
+        assert_eq!(HResult::S_OK, HResult::from(S_OK));
+        assert_eq!(HResult::S_FALSE, HResult::from(S_FALSE));
+        assert_eq!(HResult::E_FAIL, HResult::from(E_FAIL));
+        assert_eq!(HResult::E_INVALIDARG, HResult::from(E_INVALIDARG));
+        assert_eq!(HResult::E_NOINTERFACE, HResult::from(E_NOINTERFACE));
+        assert_eq!(HResult::E_POINTER, HResult::from(E_POINTER));
+        assert_eq!(HResult::E_OUTOFMEMORY, HResult::from(E_OUTOFMEMORY));
+        assert_eq!(HResult::E_NOTIMPL, HResult::from(E_NOTIMPL));
+
+        assert!(HResult::S_OK.is_success());
+        assert!(HResult::E_FAIL.is_failure());
+    }
+
+    #[test]
+    fn classify() {
+        assert_eq!(
+            HResult::E_ACCESSDENIED.classify(),
+            WellKnownError::AccessDenied
+        );
+        // Raw Win32 error codes are success codes, so they are not classified.
+        assert_eq!(
+            HResult(ERROR_FILE_NOT_FOUND).classify(),
+            WellKnownError::Other(HResult(ERROR_FILE_NOT_FOUND))
+        );
+        assert_eq!(
+            HResult::from_win32(ERROR_FILE_NOT_FOUND).classify(),
+            WellKnownError::FileNotFound
+        );
+        assert_eq!(HResult::E_INVALIDARG.classify(), WellKnownError::InvalidArg);
+        assert_eq!(
+            HResult(RPC_E_CHANGED_MODE as u32).classify(),
+            WellKnownError::ChangedMode
+        );
+
+        let other = HResult::from_win32(ERROR_BAD_EXE_FORMAT);
+        assert_eq!(other.classify(), WellKnownError::Other(other));
+        assert_eq!(
+            HResult::S_OK.classify(),
+            WellKnownError::Other(HResult::S_OK)
+        );
+    }
+
+    #[test]
+    fn hash_set() {
+        let set: std::collections::HashSet<HResult> = [
+            HResult::S_OK,
+            HResult::E_FAIL,
+            HResult::from_win32(ERROR_FILE_NOT_FOUND),
+            // Duplicates are only stored once.
+            HResult(0x8007_0002),
+        ]
+        .iter()
+        .copied()
+        .collect();
+
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&HResult::S_OK));
+        assert!(set.contains(&HResult::from(E_FAIL)));
+        assert!(set.contains(&HResult(0x8007_0002)));
+        assert!(!set.contains(&HResult::E_INVALIDARG));
+    }
+
+    #[test]
+    fn display_raw() {
+        assert_eq!(
+            HResult(0x8007_0002).display_raw().to_string(),
+            "HRESULT(0x80070002) [severity: Failure, facility: 7, code: 2]"
+        );
+        assert_eq!(
+            HResult::S_OK.display_raw().to_string(),
+            "HRESULT(0x00000000) [severity: Success, facility: 0, code: 0]"
+        );
+    }
+
+    #[test]
+    fn display_unknown_falls_back_to_raw() {
+        // A custom HRESULT with the customer bit set, which has no system message.
+        let hr = HResult(0xA0FF_1234);
+        assert!(hr.message().is_err());
+        assert_eq!(
+            hr.to_string(),
+            "HRESULT(0xA0FF1234) [severity: Failure, facility: 255, code: 4660]"
+        );
+    }
+
+    #[test]
+    fn nt_status_success() {
+        let status = NtStatus::from(STATUS_SUCCESS);
+        assert_eq!(status, NtStatus::SUCCESS);
+        assert_eq!(status.severity(), NtSeverity::Success);
+        assert!(status.is_success());
+        assert!(!status.is_error());
+        assert_eq!(status.to_hresult(), HResult(0x1000_0000));
+        assert!(status.message().is_ok());
+    }
+
+    #[test]
+    fn nt_status_access_violation() {
+        let status = NtStatus::from(STATUS_ACCESS_VIOLATION);
+        assert_eq!(status.severity(), NtSeverity::Error);
+        assert!(status.is_error());
+        assert!(!status.is_success());
+        assert!(!status.is_warning());
+        assert_eq!(status.to_hresult(), HResult(0xD000_0005));
+        assert!(status.to_hresult().is_failure());
+
+        let message = status.message().expect("failed to get message");
+        assert!(!message.as_slice().is_empty());
+    }
+
+    #[test]
+    fn nt_status_warning() {
+        let status = NtStatus::from(STATUS_BUFFER_OVERFLOW);
+        assert!(status.is_warning());
+        assert!(!status.is_information());
     }
 }